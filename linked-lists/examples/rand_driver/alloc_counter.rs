@@ -0,0 +1,96 @@
+// Without the alloc-stats feature Counting isn't installed, and only the (zero) counts are used
+#![cfg_attr(not(feature = "alloc-stats"), allow(dead_code))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Global allocator that forwards to the system allocator while keeping track of how many bytes
+// and allocations are live, and the most of each that have been live since the last reset_peak().
+// The driver is single threaded, so relaxed atomics are enough.
+pub struct Counting;
+
+// Whether Counting is installed; without the alloc-stats feature every count stays at zero
+pub const ENABLED: bool = cfg!(feature = "alloc-stats");
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static CURRENT_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+fn grow(bytes: usize) {
+    let current = CURRENT.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+fn shrink(bytes: usize) {
+    CURRENT.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+fn allocated(bytes: usize) {
+    let current = CURRENT_ALLOCS.fetch_add(1, Ordering::Relaxed) + 1;
+    PEAK_ALLOCS.fetch_max(current, Ordering::Relaxed);
+    grow(bytes);
+}
+
+fn freed(bytes: usize) {
+    CURRENT_ALLOCS.fetch_sub(1, Ordering::Relaxed);
+    shrink(bytes);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        freed(layout.size());
+    }
+
+    // Resizing in place or moving keeps the number of allocations the same
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            shrink(layout.size());
+            grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+// Bytes currently allocated
+pub fn current() -> usize {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+// Most bytes allocated at once since the last reset_peak()
+pub fn peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+// Allocations currently live
+pub fn allocations() -> usize {
+    CURRENT_ALLOCS.load(Ordering::Relaxed)
+}
+
+// Most allocations live at once since the last reset_peak()
+pub fn peak_allocations() -> usize {
+    PEAK_ALLOCS.load(Ordering::Relaxed)
+}
+
+pub fn reset_peak() {
+    PEAK.store(current(), Ordering::Relaxed);
+    PEAK_ALLOCS.store(allocations(), Ordering::Relaxed);
+}
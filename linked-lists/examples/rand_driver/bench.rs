@@ -0,0 +1,256 @@
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use linked_lists::sorted_set::SortedSet;
+use linked_lists::workload::Op;
+use crate::alloc_counter;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+// Latency summary for one kind of operation (or for all of them together)
+pub struct Stats {
+    pub op: &'static str,
+    pub count: usize,
+    pub ops_per_sec: f64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    pub max: Duration,
+}
+
+// Individual operation latencies, by kind of operation
+pub type Latencies = BTreeMap<&'static str, Vec<Duration>>;
+
+// Heap usage of a set over a whole workload, relative to what was allocated before it was created
+pub struct Memory {
+    pub peak_bytes: usize,
+    pub final_bytes: usize,
+    pub peak_allocs: usize, // number of live allocations, not bytes
+    pub final_allocs: usize,
+    pub final_len: usize,
+}
+
+pub struct Report {
+    pub implementation: String,
+    pub value_range: i32,
+    pub warmup_ops: usize,
+    pub elapsed: Duration, // wall-clock time of the measured phase
+    pub stats: Vec<Stats>, // one entry per kind of operation, then one for all operations
+    pub memory: Option<Memory>, // only measured with the alloc-stats feature
+}
+
+// Run ops against set without printing anything. The first warmup_ops operations are executed but
+// not measured; every operation after that is timed individually.
+pub fn run<S: SortedSet<i32>>(mut set: S, ops: &[Op], warmup_ops: usize) -> (Duration, Latencies) {
+    let (warmup, measured) = ops.split_at(warmup_ops.min(ops.len()));
+    for op in warmup {
+        black_box(op.apply(&mut set));
+    }
+
+    let mut latencies = Latencies::new();
+    let start = Instant::now();
+    for op in measured {
+        let op_start = Instant::now();
+        black_box(op.apply(&mut set));
+        latencies.entry(op.name()).or_default().push(op_start.elapsed());
+    }
+    (start.elapsed(), latencies)
+}
+
+// Run all of ops (warmup included) against a set from new, untimed, and see how much memory the
+// set holds at its largest and at the end. This is a separate pass so the latency samples kept by
+// run() don't count against the set. Without the alloc-stats feature there is nothing to measure
+// with, so this returns None.
+pub fn memory<S: SortedSet<i32>>(new: impl Fn() -> S, ops: &[Op]) -> Option<Memory> {
+    if !alloc_counter::ENABLED {
+        return None;
+    }
+
+    let baseline = alloc_counter::current();
+    let baseline_allocs = alloc_counter::allocations();
+    alloc_counter::reset_peak();
+
+    let mut set = new();
+    for op in ops {
+        black_box(op.apply(&mut set));
+    }
+
+    Some(Memory {
+        peak_bytes: alloc_counter::peak() - baseline,
+        final_bytes: alloc_counter::current().saturating_sub(baseline),
+        peak_allocs: alloc_counter::peak_allocations() - baseline_allocs,
+        final_allocs: alloc_counter::allocations().saturating_sub(baseline_allocs),
+        final_len: set.len(),
+    })
+}
+
+pub fn report(
+    implementation: &str,
+    value_range: i32,
+    warmup_ops: usize,
+    elapsed: Duration,
+    latencies: Latencies,
+    memory: Option<Memory>,
+) -> Report {
+    let mut all = Vec::new();
+    let mut stats = Vec::new();
+    for (op, mut times) in latencies {
+        all.extend_from_slice(&times);
+        stats.push(summarize(op, &mut times, elapsed));
+    }
+    stats.push(summarize("all", &mut all, elapsed));
+
+    Report { implementation: implementation.to_string(), value_range, warmup_ops, elapsed, stats, memory }
+}
+
+// Throughput is relative to the wall-clock time of the whole measured phase, so for a single kind
+// of operation it is the rate at which that kind got done in the mixed workload
+fn summarize(op: &'static str, times: &mut [Duration], elapsed: Duration) -> Stats {
+    times.sort();
+    let percentile = |p: f64| {
+        if times.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = ((p / 100.0) * times.len() as f64).ceil() as usize;
+        times[rank.clamp(1, times.len()) - 1]
+    };
+
+    Stats {
+        op,
+        count: times.len(),
+        ops_per_sec: times.len() as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE),
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p99: percentile(99.0),
+        p999: percentile(99.9),
+        max: times.last().copied().unwrap_or_default(),
+    }
+}
+
+const CSV_HEADER: &str = "implementation,value_range,op,count,ops_per_sec,p50_ns,p90_ns,p99_ns,p999_ns,max_ns,\
+                          peak_bytes,final_bytes,peak_allocs,final_allocs,final_len";
+
+// Byte counts in the largest binary unit that keeps them above 1
+fn bytes(n: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = n as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", n)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// One line per report, for running the same workload against several implementations
+pub fn comparison(reports: &[Report]) -> String {
+    let mut out = String::new();
+    writeln!(out, "{:<10} {:>10} {:>14} {:>10} {:>10} {:>12} {:>12} {:>10} {:>8} {:>8}",
+        "impl", "elapsed", "ops/sec", "p50", "p99", "peak mem", "final mem", "B/elem", "allocs", "len").unwrap();
+    for report in reports {
+        let Some(all) = report.stats.last() else { continue };
+        let [peak, last, per_elem, allocs, len] = match &report.memory {
+            Some(memory) => [
+                bytes(memory.peak_bytes),
+                bytes(memory.final_bytes),
+                format!("{:.1}", memory.final_bytes as f64 / memory.final_len.max(1) as f64),
+                memory.final_allocs.to_string(),
+                memory.final_len.to_string(),
+            ],
+            None => ["-"; 5].map(String::from),
+        };
+        writeln!(out, "{:<10} {:>10.3?} {:>14.0} {:>10.1?} {:>10.1?} {:>12} {:>12} {:>10} {:>8} {:>8}",
+            report.implementation, report.elapsed, all.ops_per_sec, all.p50, all.p99,
+            peak, last, per_elem, allocs, len).unwrap();
+    }
+    out
+}
+
+impl Report {
+    pub fn render(&self, format: Format, header: bool) -> String {
+        match format {
+            Format::Text => self.text(),
+            Format::Csv => self.csv(header),
+            Format::Json => self.json(),
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut out = String::new();
+        let measured: usize = self.stats.last().map_or(0, |all| all.count);
+        writeln!(out, "implementation: {}", self.implementation).unwrap();
+        writeln!(out, "value range: {}", self.value_range).unwrap();
+        writeln!(out, "operations: {} measured after {} warmup", measured, self.warmup_ops).unwrap();
+        writeln!(out, "elapsed: {:.3?}", self.elapsed).unwrap();
+        match &self.memory {
+            Some(memory) => {
+                writeln!(out, "memory: {} peak, {} final for {} elements",
+                    bytes(memory.peak_bytes), bytes(memory.final_bytes), memory.final_len).unwrap();
+                writeln!(out, "allocations: {} peak, {} final",
+                    memory.peak_allocs, memory.final_allocs).unwrap();
+            }
+            None => writeln!(out, "memory: not measured (needs the alloc-stats feature)").unwrap(),
+        }
+        writeln!(out).unwrap();
+        writeln!(out, "{:<8} {:>10} {:>14} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "op", "count", "ops/sec", "p50", "p90", "p99", "p99.9", "max").unwrap();
+        for s in &self.stats {
+            writeln!(out, "{:<8} {:>10} {:>14.0} {:>10.1?} {:>10.1?} {:>10.1?} {:>10.1?} {:>10.1?}",
+                s.op, s.count, s.ops_per_sec, s.p50, s.p90, s.p99, s.p999, s.max).unwrap();
+        }
+        out
+    }
+
+    fn csv(&self, header: bool) -> String {
+        let mut out = String::new();
+        if header {
+            writeln!(out, "{}", CSV_HEADER).unwrap();
+        }
+        // Memory columns are left empty when it wasn't measured
+        let memory = match &self.memory {
+            Some(m) => format!("{},{},{},{},{}",
+                m.peak_bytes, m.final_bytes, m.peak_allocs, m.final_allocs, m.final_len),
+            None => ",,,,".to_string(),
+        };
+        for s in &self.stats {
+            writeln!(out, "{},{},{},{},{:.1},{},{},{},{},{},{}",
+                self.implementation, self.value_range, s.op, s.count, s.ops_per_sec,
+                s.p50.as_nanos(), s.p90.as_nanos(), s.p99.as_nanos(), s.p999.as_nanos(),
+                s.max.as_nanos(), memory).unwrap();
+        }
+        out
+    }
+
+    fn json(&self) -> String {
+        let stats: Vec<String> = self.stats.iter().map(|s| {
+            format!("{{\"op\": \"{}\", \"count\": {}, \"ops_per_sec\": {:.1}, \"p50_ns\": {}, \
+                     \"p90_ns\": {}, \"p99_ns\": {}, \"p999_ns\": {}, \"max_ns\": {}}}",
+                s.op, s.count, s.ops_per_sec, s.p50.as_nanos(), s.p90.as_nanos(),
+                s.p99.as_nanos(), s.p999.as_nanos(), s.max.as_nanos())
+        }).collect();
+
+        let memory = match &self.memory {
+            Some(m) => format!("{{\"peak_bytes\": {}, \"final_bytes\": {}, \"peak_allocs\": {}, \
+                                \"final_allocs\": {}, \"final_len\": {}}}",
+                m.peak_bytes, m.final_bytes, m.peak_allocs, m.final_allocs, m.final_len),
+            None => "null".to_string(),
+        };
+
+        format!("{{\"implementation\": \"{}\", \"value_range\": {}, \"warmup_ops\": {}, \
+                 \"elapsed_ns\": {}, \"stats\": [{}], \"memory\": {}}}\n",
+            self.implementation, self.value_range, self.warmup_ops, self.elapsed.as_nanos(),
+            stats.join(", "), memory)
+    }
+}
@@ -0,0 +1,329 @@
+use clap::{Parser, ValueEnum};
+use rand::Rng;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use linked_lists::arena::SortedList as ArenaList;
+use linked_lists::cs120::List;
+use linked_lists::skiplist::SkipList;
+use linked_lists::sorted_set::SortedSet;
+use linked_lists::sorted_vec::SortedVec;
+use linked_lists::workload::{self, Generator, KeyDist, Mix, Op};
+
+mod alloc_counter;
+mod bench;
+
+// Trace header line giving how many of the operations are a benchmark's warmup
+const WARMUP_HEADER: &str = "warmup ops: ";
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::Counting = alloc_counter::Counting;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Number of random operations to perform
+    #[arg(short, long, default_value_t = 1000)]
+    num_ops: u32,
+
+    /// Range of random numbers to insert/delete from list
+    #[arg(short, long, default_value_t = 10, value_parser = clap::value_parser!(i32).range(1..))]
+    value_range: i32,
+
+    /// Relative weights of each kind of operation, e.g. insert=40,delete=40,contains=15,range=5
+    #[arg(short, long, default_value_t = Mix::default())]
+    mix: Mix,
+
+    /// How values are picked: uniform, zipf[:S], ascending, descending or hotspot[:FRACTION:PROB]
+    #[arg(short, long, default_value_t = KeyDist::Uniform)]
+    dist: KeyDist,
+
+    /// Number of values covered by each range scan
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(i32).range(1..))]
+    range_width: i32,
+
+    /// Print every print_freq operations
+    #[arg(short, long, default_value_t = 100)]
+    print_freq: u32,
+
+    /// When printing the list, also print how many heap allocations (and bytes) it holds, now and
+    /// at most so far (needs the alloc-stats feature)
+    #[arg(short = 'A', long)]
+    alloc_stats: bool,
+
+    /// Sorted set implementations to drive, comma separated, or "all". With more than one, the
+    /// same workload is benchmarked against each and compared.
+    #[arg(short, long, visible_alias = "impl", value_enum, value_delimiter = ',', default_value = "cs120")]
+    implementation: Vec<Impl>,
+
+    /// Seed for the random number generator (a random seed is chosen and printed if not given)
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Record every generated operation to this file
+    #[arg(short, long)]
+    trace: Option<PathBuf>,
+
+    /// Re-execute the operations recorded in this trace file instead of generating new ones
+    #[arg(short, long, conflicts_with_all = ["num_ops", "value_range", "seed", "mix", "dist", "range_width", "warmup_ops"])]
+    replay: Option<PathBuf>,
+
+    /// Check every operation against a BTreeSet instead of printing the list
+    #[arg(short, long, conflicts_with = "bench")]
+    check: bool,
+
+    /// Time every operation instead of printing the list, and report throughput and latency
+    #[arg(short, long)]
+    bench: bool,
+
+    /// Number of operations to run before measuring starts (in addition to num_ops). A replayed
+    /// benchmark uses the warmup recorded in its trace.
+    #[arg(short, long, default_value_t = 1000)]
+    warmup_ops: u32,
+
+    /// Format of the benchmark report
+    #[arg(short, long, value_enum, default_value_t = bench::Format::Text)]
+    format: bench::Format,
+
+    /// Append the benchmark report to this file instead of printing it
+    #[arg(short = 'o', long)]
+    bench_out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Impl {
+    Cs120,
+    Arena,
+    Skiplist,
+    Btreeset,
+    SortedVec,
+    All,
+}
+
+impl Impl {
+    const ALL: [Impl; 5] = [Impl::Cs120, Impl::Arena, Impl::Skiplist, Impl::Btreeset, Impl::SortedVec];
+
+    fn name(self) -> &'static str {
+        match self {
+            Impl::Cs120 => "cs120",
+            Impl::Arena => "arena",
+            Impl::Skiplist => "skiplist",
+            Impl::Btreeset => "btreeset",
+            Impl::SortedVec => "sorted-vec",
+            Impl::All => "all",
+        }
+    }
+}
+
+// The implementations asked for, in order and without repeats, with "all" expanded
+fn implementations(requested: &[Impl]) -> Vec<Impl> {
+    let mut impls = Vec::new();
+    for &implementation in requested {
+        let expanded = if implementation == Impl::All { &Impl::ALL[..] } else { &[implementation][..] };
+        for &implementation in expanded {
+            if !impls.contains(&implementation) {
+                impls.push(implementation);
+            }
+        }
+    }
+    impls
+}
+
+// Print the contents of the set in the same format as cs120::List::print
+fn print<S: SortedSet<i32>>(set: &S) {
+    println!("length: {}", set.len());
+    for elem in set.iter() {
+        print!("{} -> ", elem);
+    }
+    println!("end");
+}
+
+fn run<S: SortedSet<i32>>(mut set: S, ops: &[Op], print_freq: u32, alloc_stats: bool) {
+    // Allocations are counted from here, so only the set's own show up. Stdout allocates its
+    // buffer on first use, so get that out of the way first.
+    let _ = std::io::stdout().flush();
+    let baseline = alloc_counter::current();
+    let baseline_allocs = alloc_counter::allocations();
+    alloc_counter::reset_peak();
+
+    for (op, count) in ops.iter().zip(1..) {
+        op.apply(&mut set);
+
+        if count % print_freq == 0 {
+            print(&set);
+            if alloc_stats {
+                println!("allocations: {} current, {} peak ({} bytes current, {} bytes peak)",
+                    alloc_counter::allocations().saturating_sub(baseline_allocs),
+                    alloc_counter::peak_allocations().saturating_sub(baseline_allocs),
+                    alloc_counter::current().saturating_sub(baseline),
+                    alloc_counter::peak().saturating_sub(baseline));
+            }
+            println!();
+        }
+    }
+}
+
+// Run ops against a set from new, checking each one against BTreeSet. On a mismatch, print the
+// shortest history of operations we can find that still leads to one.
+fn check<S: SortedSet<i32>>(new: impl Fn() -> S, ops: &[Op]) -> Result<(), Box<dyn Error>> {
+    if let Err(divergence) = workload::check(&mut new(), ops) {
+        println!("mismatch at {}", divergence);
+
+        let minimal = workload::shrink(&new, ops);
+        println!();
+        println!("minimal history ({} operations):", minimal.len());
+        for op in &minimal {
+            println!("{}", op);
+        }
+        if let Err(divergence) = workload::check(&mut new(), &minimal) {
+            println!("which fails at {}", divergence);
+        }
+        return Err("implementation does not match BTreeSet".into());
+    }
+
+    println!("ok: all {} operations matched BTreeSet", ops.len());
+    Ok(())
+}
+
+fn main() {
+    // Process commandline arguments
+    let args = Args::parse();
+
+    if let Err(err) = drive(&args) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn drive(args: &Args) -> Result<(), Box<dyn Error>> {
+    if args.alloc_stats && !alloc_counter::ENABLED {
+        return Err("--alloc-stats needs rand_driver built with --features alloc-stats".into());
+    }
+
+    let impls = implementations(&args.implementation);
+    // Several implementations at once only make sense side by side, so that is a benchmark too
+    let bench = args.bench || (impls.len() > 1 && !args.check);
+
+    // Get the workload, either from a previous run or freshly generated. The header describes
+    // where it came from, and goes at the top of the trace file if we are recording one. Benchmarks
+    // get extra operations up front for the warmup phase, and the header records how many, so that
+    // replaying the trace measures the same operations.
+    let (ops, mut header, warmup_ops) = match &args.replay {
+        Some(path) => {
+            let trace = fs::read(path)?;
+            let ops = workload::read_trace(&trace[..])?;
+            let warmup_ops = match workload::read_trace_header(&trace[..])?
+                .iter()
+                .find_map(|line| line.strip_prefix(WARMUP_HEADER))
+            {
+                Some(count) => count.parse().map_err(|_| format!("bad warmup count {:?} in trace", count))?,
+                None => 0,
+            };
+            eprintln!("replaying {} operations from {}", ops.len(), path.display());
+            (ops, vec![format!("replay of {}", path.display())], warmup_ops)
+        }
+        None => {
+            let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+            eprintln!("seed: {}", seed);
+            let warmup_ops = if bench { args.warmup_ops } else { 0 };
+            let generator = Generator::new(seed, args.value_range, &args.mix, args.dist, args.range_width);
+            let ops = generator.take((warmup_ops + args.num_ops) as usize).collect();
+            let mut command = format!(
+                "rand_driver --seed {} --num-ops {} --value-range {} --mix {} --dist {} --range-width {}",
+                seed, args.num_ops, args.value_range, args.mix, args.dist, args.range_width
+            );
+            if bench {
+                command += &format!(" --bench --warmup-ops {}", warmup_ops);
+            }
+            (ops, vec![command], warmup_ops)
+        }
+    };
+    if warmup_ops > 0 {
+        header.push(format!("{}{}", WARMUP_HEADER, warmup_ops));
+    }
+
+    // Save the trace before running, so it is there even if the run crashes
+    if let Some(path) = &args.trace {
+        workload::write_trace(BufWriter::new(File::create(path)?), &header, &ops)?;
+    }
+
+    if bench {
+        // When replaying, the value range is whatever the trace covered
+        let value_range = match &args.replay {
+            Some(_) => ops.iter().map(|op| op.value()).max().unwrap_or(0),
+            None => args.value_range,
+        };
+        let warmup_ops = warmup_ops as usize;
+        let reports: Vec<_> = impls.iter().map(|&implementation| {
+            let ((elapsed, latencies), memory) = match implementation {
+                Impl::Cs120 => (bench::run(List::new(), &ops, warmup_ops), bench::memory(List::new, &ops)),
+                Impl::Arena => (bench::run(ArenaList::new(), &ops, warmup_ops), bench::memory(ArenaList::new, &ops)),
+                Impl::Skiplist => (bench::run(SkipList::new(), &ops, warmup_ops), bench::memory(SkipList::new, &ops)),
+                Impl::Btreeset => (bench::run(BTreeSet::new(), &ops, warmup_ops), bench::memory(BTreeSet::new, &ops)),
+                Impl::SortedVec => (bench::run(SortedVec::new(), &ops, warmup_ops), bench::memory(SortedVec::new, &ops)),
+                Impl::All => unreachable!("expanded by implementations()"),
+            };
+            bench::report(implementation.name(), value_range, warmup_ops, elapsed, latencies, memory)
+        }).collect();
+
+        // Only start a file with a CSV header, so runs can be appended to build a table
+        let (mut out, header): (Box<dyn Write>, bool) = match &args.bench_out {
+            Some(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(path)?;
+                let header = file.metadata()?.len() == 0;
+                (Box::new(file), header)
+            }
+            None => (Box::new(std::io::stdout()), true),
+        };
+        match (args.format, &reports[..]) {
+            (bench::Format::Text, [report]) => out.write_all(report.render(args.format, header).as_bytes())?,
+            (bench::Format::Text, reports) => out.write_all(bench::comparison(reports).as_bytes())?,
+            (format, reports) => {
+                for (report, i) in reports.iter().zip(0..) {
+                    out.write_all(report.render(format, header && i == 0).as_bytes())?;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if args.check {
+        let mut failed = Vec::new();
+        for &implementation in &impls {
+            if impls.len() > 1 {
+                println!("{}:", implementation.name());
+            }
+            let result = match implementation {
+                Impl::Cs120 => check(List::new, &ops),
+                Impl::Arena => check(ArenaList::new, &ops),
+                Impl::Skiplist => check(SkipList::new, &ops),
+                Impl::Btreeset => check(BTreeSet::new, &ops),
+                Impl::SortedVec => check(SortedVec::new, &ops),
+                Impl::All => unreachable!("expanded by implementations()"),
+            };
+            if result.is_err() {
+                failed.push(implementation.name());
+            }
+        }
+        return match &failed[..] {
+            [] => Ok(()),
+            _ if impls.len() == 1 => Err("implementation does not match BTreeSet".into()),
+            failed => Err(format!("{} do not match BTreeSet", failed.join(", ")).into()),
+        };
+    }
+
+    match impls[..] {
+        [Impl::Cs120] => run(List::new(), &ops, args.print_freq, args.alloc_stats),
+        [Impl::Arena] => run(ArenaList::new(), &ops, args.print_freq, args.alloc_stats),
+        [Impl::Skiplist] => run(SkipList::new(), &ops, args.print_freq, args.alloc_stats),
+        [Impl::Btreeset] => run(BTreeSet::new(), &ops, args.print_freq, args.alloc_stats),
+        [Impl::SortedVec] => run(SortedVec::new(), &ops, args.print_freq, args.alloc_stats),
+        _ => unreachable!("several implementations are benchmarked"),
+    }
+
+    Ok(())
+}
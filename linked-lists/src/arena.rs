@@ -0,0 +1,1406 @@
+use std::alloc::{self, Layout};
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use crate::cs120::{Error, Keep, DIFFERENCE, INTERSECTION, SYMMETRIC_DIFFERENCE, UNION};
+use crate::dot;
+use crate::footprint::Footprint;
+use crate::sorted_set::{after_end, before_start, SortedSet};
+
+// Arena-backed versions of cs120::List (SortedList) and second::List (Stack). Instead of a Box per
+// node, all of a list's nodes live in one Vec and link to each other by index, so walking the list
+// touches contiguous memory and inserting usually doesn't allocate at all: slots freed by
+// delete/pop are kept on a free list and reused. The public API matches the Box-based lists, so
+// the two can be benchmarked against each other.
+
+// Slots for a list's nodes, shared by both list types
+struct Slab<T> {
+    nodes: Vec<Option<Node<T>>>, // None marks a free slot
+    free: Vec<usize>,            // indices of free slots
+}
+
+struct Node<T> {
+    elem: T,
+    next: Option<usize>,
+}
+
+impl<T> Slab<T> {
+    fn new() -> Self {
+        Slab { nodes: Vec::new(), free: Vec::new() }
+    }
+
+    // Store node in a free slot if there is one, or at the end otherwise
+    fn alloc(&mut self, node: Node<T>) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // Same as alloc, but reports a Vec that can't grow as Error::AllocFailed instead of aborting.
+    // The slab is unchanged when that happens.
+    fn try_alloc(&mut self, node: Node<T>) -> Result<usize, Error> {
+        if self.free.is_empty() {
+            self.nodes.try_reserve(1).map_err(|_| Error::AllocFailed)?;
+        }
+        Ok(self.alloc(node))
+    }
+
+    // Take the node out of its slot and put the slot on the free list
+    fn release(&mut self, index: usize) -> Node<T> {
+        let node = self.nodes[index].take().expect("linked index refers to a free slot");
+        self.free.push(index);
+        node
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index].as_ref().expect("linked index refers to a free slot")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.nodes[index].as_mut().expect("linked index refers to a free slot")
+    }
+
+    // Point prev's next link (or head, if prev is None) at to
+    fn link(&mut self, head: &mut Option<usize>, prev: Option<usize>, to: Option<usize>) {
+        match prev {
+            Some(index) => self.node_mut(index).next = to,
+            None => *head = to,
+        }
+    }
+
+    // Add elem to the end of the chain starting at head, whose last node is tail
+    fn push_back(&mut self, head: &mut Option<usize>, tail: &mut Option<usize>, elem: T) {
+        let index = self.alloc(Node { elem, next: None });
+        self.link(head, *tail, Some(index));
+        *tail = Some(index);
+    }
+
+    // Every slot the Vec has room for counts, used or not, as does the free list
+    fn footprint(&self) -> Footprint {
+        let bytes_per_node = mem::size_of::<Option<Node<T>>>();
+        Footprint {
+            nodes: self.nodes.len(),
+            bytes_per_node,
+            heap_bytes: self.nodes.capacity() * bytes_per_node + self.free.capacity() * mem::size_of::<usize>(),
+        }
+    }
+
+    fn iter(&self, head: Option<usize>) -> Iter<'_, T> {
+        Iter { nodes: &self.nodes, next: head }
+    }
+}
+
+pub struct Iter<'a, T> {
+    nodes: &'a [Option<Node<T>>],
+    next: Option<usize>,
+}
+
+// Elements within a range, like cs120::Range
+pub struct Range<'a, T, R> {
+    iter: Iter<'a, T>,
+    bounds: R,
+}
+
+// Mutable iteration has to hand out &mut to several slots of the same Vec, which the borrow
+// checker can't see are distinct, so it goes through a raw pointer. The list never links a slot
+// twice, so each slot is visited (and borrowed) at most once.
+pub struct IterMut<'a, T> {
+    nodes: *mut Option<Node<T>>,
+    len: usize, // of the slot Vec, for bounds checks
+    next: Option<usize>,
+    _borrow: PhantomData<&'a mut T>,
+}
+
+// Sorted list of unique elements, with the same semantics as cs120::List
+pub struct SortedList<T> {
+    head: Option<usize>,
+    slab: Slab<T>,
+    len: usize,
+    max_len: Option<usize>, // None means no limit
+}
+
+pub struct SortedIntoIter<T>(SortedList<T>);
+
+impl<T: Ord> SortedList<T> {
+    pub fn new() -> Self {
+        SortedList { head: None, slab: Slab::new(), len: 0, max_len: None }
+    }
+
+    // Empty list that refuses to grow beyond max_len elements
+    pub fn with_max_len(max_len: usize) -> Self {
+        SortedList { head: None, slab: Slab::new(), len: 0, max_len: Some(max_len) }
+    }
+
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    // Change (or remove) the limit. As with cs120::List, lowering it below the current length
+    // only stops further inserts.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.max_len = max_len;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // Number of node slots allocated, including free ones waiting to be reused
+    pub fn capacity(&self) -> usize {
+        self.slab.nodes.len()
+    }
+
+    // Heap memory held by the list: its slots (including free ones) and free list
+    pub fn memory_footprint(&self) -> Footprint {
+        self.slab.footprint()
+    }
+
+    // Smallest element in the list
+    pub fn first(&self) -> Option<&T> {
+        self.head.map(|index| &self.slab.node(index).elem)
+    }
+
+    // Largest element in the list (requires walking the whole list)
+    pub fn last(&self) -> Option<&T> {
+        self.iter().last()
+    }
+
+    // Check whether elem is in the list, stopping as soon as we reach a value greater than elem
+    pub fn contains(&self, elem: &T) -> bool {
+        for cur in self.iter() {
+            if cur == elem {
+                return true;
+            } else if cur > elem {
+                return false;
+            }
+        }
+        false
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.slab.iter(self.head)
+    }
+
+    // Iterate over the elements within range, in sorted order. A range whose start lies after its
+    // end is simply empty.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        let mut iter = self.iter();
+        while let Some(index) = iter.next {
+            let node = self.slab.node(index);
+            if !before_start(range.start_bound(), &node.elem) {
+                break;
+            }
+            iter.next = node.next;
+        }
+        Range { iter, bounds: range }
+    }
+
+    pub fn count_in_range<R: RangeBounds<T>>(&self, range: R) -> usize {
+        self.range(range).count()
+    }
+
+    // Largest element less than or equal to elem
+    pub fn floor(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Unbounded, Bound::Included(elem))).last()
+    }
+
+    // Smallest element greater than or equal to elem
+    pub fn ceiling(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Included(elem), Bound::Unbounded)).next()
+    }
+
+    // Largest element strictly less than elem
+    pub fn predecessor(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Unbounded, Bound::Excluded(elem))).last()
+    }
+
+    // Smallest element strictly greater than elem
+    pub fn successor(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Excluded(elem), Bound::Unbounded)).next()
+    }
+
+    // Apply f to every element in order, then restore the ordering the same way cs120::List does:
+    // re-sort if anything moved out of place, keeping only the first of any equal elements
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            let node = self.slab.node_mut(index);
+            f(&mut node.elem);
+            cur = node.next;
+        }
+
+        let mut prev: Option<&T> = None;
+        let mut still_sorted = true;
+        for cur in self.iter() {
+            if prev.is_some_and(|prev| prev >= cur) {
+                still_sorted = false;
+                break;
+            }
+            prev = Some(cur);
+        }
+
+        if !still_sorted {
+            self.resort();
+        }
+    }
+
+    // Stably sort the linked slot indices by element and relink them in that order. The elements
+    // stay in their slots; duplicates after the first are released.
+    fn resort(&mut self) {
+        let mut indices = Vec::with_capacity(self.len);
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            indices.push(index);
+            cur = self.slab.node(index).next;
+        }
+        indices.sort_by(|&a, &b| self.slab.node(a).elem.cmp(&self.slab.node(b).elem));
+
+        self.head = None;
+        self.len = 0;
+        let mut tail = None;
+        for index in indices {
+            if tail.is_some_and(|tail| self.slab.node(tail).elem == self.slab.node(index).elem) {
+                self.slab.release(index);
+                continue;
+            }
+            self.slab.link(&mut self.head, tail, Some(index));
+            tail = Some(index);
+            self.len += 1;
+        }
+        self.slab.link(&mut self.head, tail, None);
+    }
+
+    // Insert an element, returning true if it was inserted and false if it was not (because it was
+    // already present or the list is full). Panics if the slot Vec can't grow; use try_insert to
+    // handle that instead.
+    pub fn insert(&mut self, elem: T) -> bool {
+        match self.try_insert(elem) {
+            Ok(()) => true,
+            Err(Error::Duplicate) | Err(Error::CapacityExceeded { .. }) => false,
+            Err(Error::NotFound) => unreachable!(),
+            Err(Error::AllocFailed) => alloc::handle_alloc_error(Layout::new::<Option<Node<T>>>()),
+        }
+    }
+
+    // Insert an element, with the same errors as cs120::List::try_insert. The list is unchanged
+    // whenever an Error is returned.
+    pub fn try_insert(&mut self, elem: T) -> Result<(), Error> {
+        // Full list: nothing can be inserted, but still report duplicates as such
+        if let Some(max_len) = self.max_len {
+            if self.len >= max_len {
+                if self.contains(&elem) {
+                    return Err(Error::Duplicate);
+                }
+                return Err(Error::CapacityExceeded { max_len });
+            }
+        }
+
+        let (prev, cur) = self.find(&elem);
+        if cur.is_some_and(|index| self.slab.node(index).elem == elem) {
+            return Err(Error::Duplicate);
+        }
+
+        let index = self.slab.try_alloc(Node { elem, next: cur })?;
+        self.slab.link(&mut self.head, prev, Some(index));
+        self.len += 1;
+        Ok(())
+    }
+
+    // Remove an element, returning true if it was removed and false if it was not present
+    pub fn delete(&mut self, elem: &T) -> bool {
+        self.try_delete(elem).is_ok()
+    }
+
+    // Same as delete, but reports a missing element as Error::NotFound
+    pub fn try_delete(&mut self, elem: &T) -> Result<(), Error> {
+        let (prev, cur) = self.find(elem);
+        match cur {
+            Some(index) if self.slab.node(index).elem == *elem => {
+                let node = self.slab.release(index);
+                self.slab.link(&mut self.head, prev, node.next);
+                self.len -= 1;
+                Ok(())
+            }
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    // The last node before elem's position and the first node at or after it
+    fn find(&self, elem: &T) -> (Option<usize>, Option<usize>) {
+        let mut prev = None;
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            let node = self.slab.node(index);
+            if node.elem >= *elem {
+                break;
+            }
+            prev = cur;
+            cur = node.next;
+        }
+        (prev, cur)
+    }
+}
+
+// Set algebra, as for cs120::List. Nodes can't move between slabs, so even the into_* variants
+// build the result in a new slab; they just move the elements rather than cloning them.
+impl<T: Ord> SortedList<T> {
+    pub fn into_union(self, other: SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.into_iter(), other.into_iter(), UNION)
+    }
+
+    pub fn into_intersection(self, other: SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.into_iter(), other.into_iter(), INTERSECTION)
+    }
+
+    pub fn into_difference(self, other: SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.into_iter(), other.into_iter(), DIFFERENCE)
+    }
+
+    pub fn into_symmetric_difference(self, other: SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.into_iter(), other.into_iter(), SYMMETRIC_DIFFERENCE)
+    }
+
+    // True if every element of self is also in other
+    pub fn is_subset(&self, other: &SortedList<T>) -> bool {
+        let mut other_iter = other.iter().peekable();
+        for elem in self.iter() {
+            // Skip past the smaller elements of other; the next one has to be a match
+            while other_iter.next_if(|other_elem| *other_elem < elem).is_some() {}
+            if other_iter.next_if_eq(&elem).is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
+    // True if self and other have no elements in common
+    pub fn is_disjoint(&self, other: &SortedList<T>) -> bool {
+        let mut iter = self.iter().peekable();
+        let mut other_iter = other.iter().peekable();
+        while let (Some(elem), Some(other_elem)) = (iter.peek(), other_iter.peek()) {
+            if elem < other_elem {
+                iter.next();
+            } else if elem > other_elem {
+                other_iter.next();
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<T: Ord + Clone> SortedList<T> {
+    pub fn union(&self, other: &SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.iter().cloned(), other.iter().cloned(), UNION)
+    }
+
+    pub fn intersection(&self, other: &SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.iter().cloned(), other.iter().cloned(), INTERSECTION)
+    }
+
+    pub fn difference(&self, other: &SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.iter().cloned(), other.iter().cloned(), DIFFERENCE)
+    }
+
+    pub fn symmetric_difference(&self, other: &SortedList<T>) -> SortedList<T> {
+        merge_sorted(self.iter().cloned(), other.iter().cloned(), SYMMETRIC_DIFFERENCE)
+    }
+}
+
+// Merge two increasing sequences of unique elements into a new list, keeping the elements that
+// keep says to. The result is built front to back, so its slots are in order too.
+fn merge_sorted<T: Ord>(left: impl Iterator<Item = T>, right: impl Iterator<Item = T>, keep: Keep) -> SortedList<T> {
+    let mut left = left.peekable();
+    let mut right = right.peekable();
+
+    let mut result = SortedList::new();
+    let mut tail = None;
+    loop {
+        let (elem, kept) = match (left.peek(), right.peek()) {
+            (Some(left_elem), Some(right_elem)) if left_elem < right_elem => (left.next(), keep.left),
+            (Some(left_elem), Some(right_elem)) if left_elem > right_elem => (right.next(), keep.right),
+            (Some(_), Some(_)) => {
+                right.next();
+                (left.next(), keep.both)
+            }
+            (Some(_), None) => (left.next(), keep.left),
+            (None, Some(_)) => (right.next(), keep.right),
+            (None, None) => break,
+        };
+
+        if let (Some(elem), true) = (elem, kept) {
+            result.slab.push_back(&mut result.head, &mut tail, elem);
+            result.len += 1;
+        }
+    }
+    result
+}
+
+impl<T: Ord + Display> SortedList<T> {
+    // Print the length and contents of the list
+    pub fn print(&self) {
+        println!("length: {}", self.len);
+        println!("{}", self);
+    }
+
+    // The list's nodes and next links as a Graphviz DOT graph (see dot::render)
+    pub fn to_dot(&self) -> String {
+        dot::render("arena::SortedList", self.iter())
+    }
+}
+
+impl<T: Ord> Default for SortedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Elements in order, as `a -> b -> end`
+impl<T: Display> Display for SortedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for elem in self.slab.iter(self.head) {
+            write!(f, "{} -> ", elem)?;
+        }
+        write!(f, "end")
+    }
+}
+
+impl<T: Debug> Debug for SortedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.slab.iter(self.head)).finish()
+    }
+}
+
+impl<T> IntoIterator for SortedList<T> {
+    type Item = T;
+    type IntoIter = SortedIntoIter<T>;
+
+    fn into_iter(self) -> SortedIntoIter<T> {
+        SortedIntoIter(self)
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a SortedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Ord> SortedSet<T> for SortedList<T> {
+    type Iter<'a> = Iter<'a, T> where T: 'a;
+    type Range<'a, R> = Range<'a, T, R> where T: 'a, R: RangeBounds<T>;
+
+    fn insert(&mut self, elem: T) -> bool {
+        SortedList::insert(self, elem)
+    }
+
+    fn delete(&mut self, elem: &T) -> bool {
+        SortedList::delete(self, elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        SortedList::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        SortedList::iter(self)
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        SortedList::range(self, range)
+    }
+}
+
+impl<T> Iterator for SortedIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let list = &mut self.0;
+        list.head.map(|index| {
+            let node = list.slab.release(index);
+            list.head = node.next;
+            list.len -= 1;
+            node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+// Stack with the same API as second::List
+pub struct Stack<T> {
+    head: Option<usize>,
+    slab: Slab<T>,
+    len: usize,
+}
+
+pub struct StackIntoIter<T>(Stack<T>);
+
+// Position in a stack that can edit it as it goes, like second::CursorMut. prev is the node before
+// the current one, or None when the current one is the top of the stack.
+pub struct CursorMut<'a, T> {
+    list: &'a mut Stack<T>,
+    prev: Option<usize>,
+    index: usize,
+}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack { head: None, slab: Slab::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // Number of node slots allocated, including free ones waiting to be reused
+    pub fn capacity(&self) -> usize {
+        self.slab.nodes.len()
+    }
+
+    // Heap memory held by the list: its slots (including free ones) and free list
+    pub fn memory_footprint(&self) -> Footprint {
+        self.slab.footprint()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.head.map(|index| &self.slab.node(index).elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.map(|index| &mut self.slab.node_mut(index).elem)
+    }
+
+    pub fn push(&mut self, elem: T) {
+        self.head = Some(self.slab.alloc(Node { elem, next: self.head }));
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.head.map(|index| {
+            let node = self.slab.release(index);
+            self.head = node.next;
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.slab.iter(self.head)
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let nodes = &mut self.slab.nodes;
+        IterMut { nodes: nodes.as_mut_ptr(), len: nodes.len(), next: self.head, _borrow: PhantomData }
+    }
+
+    // Cursor whose current element is the top of the stack
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { list: self, prev: None, index: 0 }
+    }
+
+    // Reverse the stack in place by turning every next link around
+    pub fn reverse(&mut self) {
+        let mut reversed = None;
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            let node = self.slab.node_mut(index);
+            cur = node.next;
+            node.next = reversed;
+            reversed = Some(index);
+        }
+        self.head = reversed;
+    }
+
+    // Keep only the elements for which keep returns true, in their original order
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut prev = None;
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            cur = self.slab.node(index).next;
+            if keep(&self.slab.node(index).elem) {
+                prev = Some(index);
+            } else {
+                self.slab.release(index);
+                self.slab.link(&mut self.head, prev, cur);
+                self.len -= 1;
+            }
+        }
+    }
+
+    // Split the stack in two at index at, as second::List::split_off does. The elements after at
+    // move into the new stack's own slab. Panics if at is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> Stack<T> {
+        let mut prev = None;
+        let mut cur = self.head;
+        for _ in 0..at {
+            let index = cur.expect("split_off index out of bounds");
+            prev = Some(index);
+            cur = self.slab.node(index).next;
+        }
+        self.slab.link(&mut self.head, prev, None);
+
+        let mut rest = Stack::new();
+        let mut tail = None;
+        while let Some(index) = cur {
+            let node = self.slab.release(index);
+            cur = node.next;
+            rest.slab.push_back(&mut rest.head, &mut tail, node.elem);
+            rest.len += 1;
+        }
+        self.len -= rest.len;
+        rest
+    }
+
+    // Move all of other's elements to the bottom of this stack, in their current order, leaving
+    // other empty
+    pub fn append(&mut self, other: &mut Stack<T>) {
+        let mut tail = None;
+        let mut cur = self.head;
+        while let Some(index) = cur {
+            tail = cur;
+            cur = self.slab.node(index).next;
+        }
+
+        while let Some(elem) = other.pop() {
+            self.slab.push_back(&mut self.head, &mut tail, elem);
+            self.len += 1;
+        }
+    }
+
+    // Stable bottom-up merge sort that relinks the existing slots, the same way
+    // second::List::sort_by relinks its nodes
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        // bins[i] is either empty or the head of a sorted run of 2^i nodes
+        let mut bins: Vec<Option<usize>> = Vec::new();
+        while let Some(index) = self.head {
+            let node = self.slab.node_mut(index);
+            self.head = node.next.take();
+            let mut run = Some(index);
+
+            let mut i = 0;
+            while i < bins.len() && bins[i].is_some() {
+                run = merge_runs(&mut self.slab, bins[i].take(), run, &mut compare);
+                i += 1;
+            }
+            if i == bins.len() {
+                bins.push(None);
+            }
+            bins[i] = run;
+        }
+
+        let mut sorted = None;
+        for earlier in bins {
+            sorted = merge_runs(&mut self.slab, earlier, sorted, &mut compare);
+        }
+        self.head = sorted;
+    }
+}
+
+impl<T: Ord> Stack<T> {
+    // Sort into increasing order from the top down (see sort_by)
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+}
+
+// Merge two sorted runs of slots into one, returning its head. On ties the node from earlier wins.
+fn merge_runs<T, F: FnMut(&T, &T) -> Ordering>(
+    slab: &mut Slab<T>,
+    mut earlier: Option<usize>,
+    mut later: Option<usize>,
+    compare: &mut F,
+) -> Option<usize> {
+    let mut head = None;
+    let mut tail = None;
+    while let (Some(a), Some(b)) = (earlier, later) {
+        let index = if compare(&slab.node(b).elem, &slab.node(a).elem) != Ordering::Less {
+            earlier = slab.node(a).next;
+            a
+        } else {
+            later = slab.node(b).next;
+            b
+        };
+        slab.link(&mut head, tail, Some(index));
+        tail = Some(index);
+    }
+
+    // One side has run out; the other is already sorted and can be attached as it is
+    slab.link(&mut head, tail, earlier.or(later));
+    head
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    // Slot of the current element, or None past the end
+    fn current_slot(&self) -> Option<usize> {
+        match self.prev {
+            Some(prev) => self.list.slab.node(prev).next,
+            None => self.list.head,
+        }
+    }
+
+    // Index of the current element from the top, or None past the end
+    pub fn index(&self) -> Option<usize> {
+        self.current_slot().map(|_| self.index)
+    }
+
+    // Move on to the next element. Past the end this does nothing.
+    pub fn move_next(&mut self) {
+        if let Some(current) = self.current_slot() {
+            self.prev = Some(current);
+            self.index += 1;
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        let current = self.current_slot()?;
+        Some(&mut self.list.slab.node_mut(current).elem)
+    }
+
+    // The element after the current one
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = self.list.slab.node(self.current_slot()?).next?;
+        Some(&mut self.list.slab.node_mut(next).elem)
+    }
+
+    // Swap elem in for the current element, returning the old one. Past the end there is nothing
+    // to replace, and elem is handed back as the error.
+    pub fn replace_current(&mut self, elem: T) -> Result<T, T> {
+        match self.current() {
+            Some(current) => Ok(mem::replace(current, elem)),
+            None => Err(elem),
+        }
+    }
+
+    // Insert elem just before the current element (or on the end of the stack, if the cursor is
+    // past the end). The cursor stays on the same element, whose index goes up by one.
+    pub fn insert_before(&mut self, elem: T) {
+        let next = self.current_slot();
+        let list = &mut *self.list;
+        let index = list.slab.alloc(Node { elem, next });
+        list.slab.link(&mut list.head, self.prev, Some(index));
+        list.len += 1;
+        self.prev = Some(index);
+        self.index += 1;
+    }
+
+    // Insert elem just after the current element, without moving the cursor. Past the end it goes
+    // on the end of the stack, the same as insert_before.
+    pub fn insert_after(&mut self, elem: T) {
+        match self.current_slot() {
+            Some(current) => {
+                self.insert_after_slot(current, elem);
+            }
+            None => self.insert_before(elem),
+        }
+    }
+
+    // Link elem in after the node in slot prev, returning its slot
+    fn insert_after_slot(&mut self, prev: usize, elem: T) -> usize {
+        let slab = &mut self.list.slab;
+        let next = slab.node(prev).next;
+        let index = slab.alloc(Node { elem, next });
+        slab.node_mut(prev).next = Some(index);
+        self.list.len += 1;
+        index
+    }
+
+    // Unlink the current element and return it. The element after it becomes the current one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let current = self.current_slot()?;
+        let list = &mut *self.list;
+        let node = list.slab.release(current);
+        list.slab.link(&mut list.head, self.prev, node.next);
+        list.len -= 1;
+        Some(node.elem)
+    }
+
+    // Move all of other's elements in just before the current element, in their current order.
+    // The cursor stays on the same element.
+    pub fn splice_before(&mut self, other: Stack<T>) {
+        for elem in other {
+            self.insert_before(elem);
+        }
+    }
+
+    // Move all of other's elements in just after the current element, in their current order,
+    // without moving the cursor. Past the end they go on the end of the stack.
+    pub fn splice_after(&mut self, other: Stack<T>) {
+        match self.current_slot() {
+            Some(current) => {
+                let mut prev = current;
+                for elem in other {
+                    prev = self.insert_after_slot(prev, elem);
+                }
+            }
+            None => self.splice_before(other),
+        }
+    }
+}
+
+impl<T: Display> Stack<T> {
+    // The stack's nodes and next links as a Graphviz DOT graph (see dot::render)
+    pub fn to_dot(&self) -> String {
+        dot::render("arena::Stack", self.iter())
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Elements from the top of the stack down, as `a -> b -> end`
+impl<T: Display> Display for Stack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for elem in self.iter() {
+            write!(f, "{} -> ", elem)?;
+        }
+        write!(f, "end")
+    }
+}
+
+impl<T: Debug> Debug for Stack<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Copies keep the same order, top to bottom, in a slab with no free slots
+impl<T: Clone> Clone for Stack<T> {
+    fn clone(&self) -> Self {
+        let mut list = Stack::new();
+        let mut tail = None;
+        for elem in self.iter() {
+            list.slab.push_back(&mut list.head, &mut tail, elem.clone());
+        }
+        list.len = self.len;
+        list
+    }
+}
+
+// Stacks are equal if they hold equal elements in the same order, wherever their slots are
+impl<T: PartialEq> PartialEq for Stack<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Stack<T> {}
+
+impl<T: Hash> Hash for Stack<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Elements then length, the same as second::List
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+        state.write_usize(self.len);
+    }
+}
+
+// Pushes each element in turn, so the last element of the iterator ends up on top
+impl<T> FromIterator<T> for Stack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Stack::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for Stack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T> IntoIterator for Stack<T> {
+    type Item = T;
+    type IntoIter = StackIntoIter<T>;
+
+    fn into_iter(self) -> StackIntoIter<T> {
+        StackIntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Stack<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> Iterator for StackIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|index| {
+            let node = self.nodes[index].as_ref().expect("linked index refers to a free slot");
+            self.next = node.next;
+            &node.elem
+        })
+    }
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Range<'a, T, R> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elem = self.iter.next()?;
+        if after_end(self.bounds.end_bound(), elem) {
+            // Everything after this is larger still, so we are done
+            self.iter.next = None;
+            return None;
+        }
+        Some(elem)
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|index| {
+            assert!(index < self.len, "linked index out of bounds");
+            // The pointer comes from a &mut borrow of the Vec that lives for 'a, and no slot is
+            // linked twice, so this is the only reference to the slot
+            let slot = unsafe { &mut *self.nodes.add(index) };
+            let node = slot.as_mut().expect("linked index refers to a free slot");
+            self.next = node.next;
+            &mut node.elem
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SortedList, Stack};
+    use crate::cs120::Error;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[test]
+    fn sorted_basics() {
+        let mut list = SortedList::new();
+        assert!(list.is_empty());
+        for elem in [20, 10, 30, 15] {
+            assert!(list.insert(elem));
+        }
+        assert!(!list.insert(15));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 15, 20, 30]);
+        assert_eq!(list.first(), Some(&10));
+        assert_eq!(list.last(), Some(&30));
+        assert_eq!(list.len(), 4);
+
+        assert!(list.delete(&10));
+        assert!(list.delete(&30));
+        assert!(!list.delete(&30));
+        assert!(list.contains(&20));
+        assert!(!list.contains(&10));
+        assert_eq!(format!("{:?}", list), "[15, 20]");
+        assert_eq!(list.count_in_range(16..), 1);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [15, 20]);
+    }
+
+    #[test]
+    fn sorted_reuses_free_slots() {
+        let mut list = SortedList::new();
+        for elem in 0..10 {
+            list.insert(elem);
+        }
+        for elem in 0..5 {
+            list.delete(&elem);
+        }
+        for elem in 10..15 {
+            list.insert(elem);
+        }
+        assert_eq!(list.capacity(), 10);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), (5..15).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sorted_memory_footprint() {
+        let mut list = SortedList::new();
+        for elem in 0..10 {
+            list.insert(elem);
+        }
+        list.delete(&3);
+
+        // The freed slot is still part of the arena until it is reused
+        let footprint = list.memory_footprint();
+        assert_eq!(footprint.nodes, 10);
+        assert!(footprint.heap_bytes >= 10 * footprint.bytes_per_node + std::mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn sorted_errors() {
+        let mut list = SortedList::with_max_len(3);
+        assert_eq!(list.max_len(), Some(3));
+        assert_eq!(list.try_insert(20), Ok(()));
+        assert_eq!(list.try_insert(10), Ok(()));
+        assert_eq!(list.try_insert(20), Err(Error::Duplicate));
+        assert_eq!(list.try_insert(30), Ok(()));
+
+        // Full list rejects new elements, but still reports duplicates as duplicates
+        assert_eq!(list.try_insert(40), Err(Error::CapacityExceeded { max_len: 3 }));
+        assert_eq!(list.try_insert(10), Err(Error::Duplicate));
+        assert!(!list.insert(40));
+
+        assert_eq!(list.try_delete(&20), Ok(()));
+        assert_eq!(list.try_delete(&20), Err(Error::NotFound));
+        assert!(list.insert(40));
+
+        list.set_max_len(None);
+        assert_eq!(list.try_insert(50), Ok(()));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 30, 40, 50]);
+    }
+
+    #[test]
+    fn sorted_neighbours() {
+        let list: SortedList<i32> = [10, 20, 30].into_iter().fold(SortedList::new(), |mut list, elem| {
+            list.insert(elem);
+            list
+        });
+        assert_eq!(list.floor(&20), Some(&20));
+        assert_eq!(list.floor(&25), Some(&20));
+        assert_eq!(list.floor(&5), None);
+        assert_eq!(list.ceiling(&25), Some(&30));
+        assert_eq!(list.ceiling(&35), None);
+        assert_eq!(list.predecessor(&20), Some(&10));
+        assert_eq!(list.predecessor(&10), None);
+        assert_eq!(list.successor(&20), Some(&30));
+        assert_eq!(list.successor(&30), None);
+    }
+
+    #[test]
+    fn sorted_for_each_mut() {
+        let mut list = SortedList::new();
+        for elem in [1, 2, 3, 4, 5] {
+            list.insert(elem);
+        }
+
+        // Order-preserving change: nothing to re-sort
+        list.for_each_mut(|elem| *elem *= 10);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [10, 20, 30, 40, 50]);
+
+        // Reversing the order and collapsing neighbours means re-sorting and dropping duplicates,
+        // whose slots go back on the free list
+        list.for_each_mut(|elem| *elem = -(*elem / 20));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [-2, -1, 0]);
+        assert_eq!(list.len(), 3);
+        list.insert(7);
+        list.insert(8);
+        assert_eq!(list.capacity(), 5);
+    }
+
+    #[test]
+    fn sorted_formatting() {
+        let mut list = SortedList::new();
+        assert_eq!(list.to_string(), "end");
+        for elem in [20, 10, 30] {
+            list.insert(elem);
+        }
+        assert_eq!(list.to_string(), "10 -> 20 -> 30 -> end");
+        assert_eq!(format!("{:?}", list), "[10, 20, 30]");
+
+        let dot = list.to_dot();
+        assert!(dot.starts_with("digraph \"arena::SortedList\" {"));
+        assert!(dot.contains("n2:next:c -> end;"));
+    }
+
+    // Random operations applied to both lists, including set algebra between pairs of lists,
+    // have to give the same results as cs120::List
+    #[test]
+    fn sorted_matches_cs120() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut list = SortedList::new();
+        let mut model = crate::cs120::List::new();
+        for _ in 0..2000 {
+            let value = rng.gen_range(0..64);
+            match rng.gen_range(0..6) {
+                0 | 1 => assert_eq!(list.try_insert(value), model.try_insert(value)),
+                2 => assert_eq!(list.try_delete(&value), model.try_delete(&value)),
+                3 => {
+                    assert_eq!(list.floor(&value), model.floor(&value));
+                    assert_eq!(list.ceiling(&value), model.ceiling(&value));
+                    assert_eq!(list.predecessor(&value), model.predecessor(&value));
+                    assert_eq!(list.successor(&value), model.successor(&value));
+                }
+                4 => {
+                    let shift = rng.gen_range(-3..=3);
+                    list.for_each_mut(|elem| *elem = (*elem + shift) / 2 * 2);
+                    model.for_each_mut(|elem| *elem = (*elem + shift) / 2 * 2);
+                }
+                _ => {
+                    let others: Vec<i32> = (0..rng.gen_range(0..20)).map(|_| rng.gen_range(0..64)).collect();
+                    let (mut other, mut other_model) = (SortedList::new(), crate::cs120::List::new());
+                    for elem in others {
+                        other.insert(elem);
+                        other_model.insert(elem);
+                    }
+                    assert!(list.union(&other).iter().eq(model.union(&other_model).iter()));
+                    assert!(list.intersection(&other).iter().eq(model.intersection(&other_model).iter()));
+                    assert!(list.difference(&other).iter().eq(model.difference(&other_model).iter()));
+                    assert_eq!(list.is_subset(&other), model.is_subset(&other_model));
+                    assert_eq!(list.is_disjoint(&other), model.is_disjoint(&other_model));
+                    list = list.into_symmetric_difference(other);
+                    model = model.into_symmetric_difference(other_model);
+                }
+            }
+            assert_eq!(list.len(), model.len());
+            assert!(list.iter().eq(model.iter()));
+        }
+    }
+
+    #[test]
+    fn conformance() {
+        crate::sorted_set::conformance::run(SortedList::new);
+    }
+
+    #[test]
+    fn stack_basics() {
+        let mut stack = Stack::new();
+
+        // Check that peeking and popping an empty stack gives None
+        assert_eq!(stack.peek(), None);
+        assert_eq!(stack.pop(), None);
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.peek(), Some(&3));
+        if let Some(value) = stack.peek_mut() {
+            *value = 42
+        }
+        assert_eq!(stack.pop(), Some(42));
+        assert_eq!(stack.pop(), Some(2));
+
+        // The freed slots get used again
+        stack.push(4);
+        stack.push(5);
+        assert_eq!(stack.capacity(), 3);
+        assert_eq!(stack.len(), 3);
+
+        assert_eq!(stack.pop(), Some(5));
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn stack_iterators() {
+        let mut stack = Stack::new();
+        stack.push(1); stack.push(2); stack.push(3);
+
+        assert_eq!(stack.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+        for elem in stack.iter_mut() {
+            *elem *= 10;
+        }
+        for elem in &mut stack {
+            *elem += 1;
+        }
+        assert_eq!(format!("{:?}", stack), "[31, 21, 11]");
+        assert_eq!(stack.into_iter().collect::<Vec<_>>(), [31, 21, 11]);
+    }
+
+    #[test]
+    fn stack_traits() {
+        let stack: Stack<i32> = (1..=3).collect();
+        assert_eq!(stack.peek(), Some(&3));
+
+        let mut copy = stack.clone();
+        assert_eq!(copy, stack);
+        copy.pop();
+        copy.extend([4]);
+        assert_ne!(copy, stack);
+        assert_eq!(copy.iter().copied().collect::<Vec<_>>(), [4, 2, 1]);
+
+        // Equal stacks hash the same however their slots are laid out
+        let mut reused = Stack::new();
+        reused.push(9);
+        reused.pop();
+        reused.extend(1..=3);
+        let hash_of = |stack: &Stack<i32>| {
+            let mut hasher = DefaultHasher::new();
+            stack.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(reused, stack);
+        assert_eq!(hash_of(&reused), hash_of(&stack));
+
+        assert_eq!(stack.to_string(), "3 -> 2 -> 1 -> end");
+        assert!(stack.to_dot().starts_with("digraph \"arena::Stack\" {"));
+    }
+
+    #[test]
+    fn stack_cursor() {
+        let mut stack: Stack<i32> = [5, 3, 1].into_iter().collect();
+        let mut cursor = stack.cursor_front_mut();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.insert_before(0);
+        cursor.insert_after(2);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+
+        cursor.move_next();
+        assert_eq!(cursor.replace_current(20), Ok(2));
+        assert_eq!(cursor.remove_current(), Some(20));
+        assert_eq!(cursor.current(), Some(&mut 3));
+        cursor.splice_after([6, 4].into_iter().collect());
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.splice_before([8, 7].into_iter().collect());
+        assert_eq!(cursor.current(), Some(&mut 5));
+
+        // Past the end everything goes on the end
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.replace_current(0), Err(0));
+        cursor.insert_after(9);
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), [0, 1, 3, 4, 6, 7, 8, 5, 9]);
+        assert_eq!(stack.len(), 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "split_off index out of bounds")]
+    fn stack_split_off_past_end() {
+        Stack::from_iter([1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn stack_sort_is_stable() {
+        let elems: Vec<(i32, usize)> = (0..200).map(|i| ((i * 7) % 5, i as usize)).collect();
+        let mut stack: Stack<_> = elems.iter().rev().copied().collect();
+        stack.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut expected = elems;
+        expected.sort_by_key(|elem| elem.0);
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), expected);
+    }
+
+    // Random operations applied to both stacks, including cursor edits and the whole-list
+    // operations, have to leave them holding the same elements in the same order
+    #[test]
+    fn stack_matches_second() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut stack = Stack::new();
+        let mut model = crate::second::List::new();
+        for i in 0..2000 {
+            match rng.gen_range(0..10) {
+                0..=3 => {
+                    stack.push(i);
+                    model.push(i);
+                }
+                4 | 5 => {
+                    assert_eq!(stack.pop(), model.pop());
+                }
+                6 => {
+                    let modulus = rng.gen_range(2..5);
+                    stack.retain(|elem| elem % modulus != 0);
+                    model.retain(|elem| elem % modulus != 0);
+                }
+                7 => {
+                    let at = rng.gen_range(0..=stack.len());
+                    let (mut rest, mut model_rest) = (stack.split_off(at), model.split_off(at));
+                    if rng.gen_bool(0.5) {
+                        rest.reverse();
+                        model_rest.reverse();
+                    }
+                    stack.append(&mut rest);
+                    model.append(&mut model_rest);
+                }
+                8 => {
+                    let steps = rng.gen_range(0..=stack.len());
+                    let (mut cursor, mut model_cursor) = (stack.cursor_front_mut(), model.cursor_front_mut());
+                    for _ in 0..steps {
+                        cursor.move_next();
+                        model_cursor.move_next();
+                    }
+                    if rng.gen_bool(0.5) {
+                        cursor.insert_after(-i);
+                        model_cursor.insert_after(-i);
+                    } else {
+                        assert_eq!(cursor.remove_current(), model_cursor.remove_current());
+                    }
+                }
+                _ => {
+                    stack.sort_by(|a: &i32, b| (a % 7).cmp(&(b % 7)));
+                    model.sort_by(|a: &i32, b| (a % 7).cmp(&(b % 7)));
+                }
+            }
+            assert_eq!(stack.len(), model.iter().count());
+            assert!(stack.iter().eq(model.iter()));
+        }
+    }
+
+    // None of the operations recurse, so they cope with stacks far longer than the call stack
+    #[test]
+    fn stack_long_lists() {
+        let mut stack: Stack<i32> = (0..200_000).collect();
+        stack.reverse();
+        stack.sort_by(|a, b| b.cmp(a));
+        assert_eq!(stack.peek(), Some(&199_999));
+        stack.retain(|elem| elem % 2 == 0);
+        let mut rest = stack.split_off(50_000);
+        stack.append(&mut rest);
+        assert_eq!(stack.iter_mut().count(), 100_000);
+    }
+}
@@ -0,0 +1,267 @@
+use std::cmp::Ordering;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+
+use parking_lot::{ArcMutexGuard, Mutex, RawMutex};
+
+use crate::footprint::Footprint;
+
+// Sorted set of unique elements that many threads can use at once through a shared reference.
+// Every node has its own lock, and operations walk the list hand over hand (lock coupling): the
+// lock on the next node is taken before the one on the current node is released. So threads
+// working on different parts of the list don't wait for each other, but no thread can ever see a
+// node being unlinked or linked in underneath it.
+//
+// Nodes are reference counted so that a guard can own its node (parking_lot's ArcMutexGuard)
+// instead of borrowing it from the previous node, which would tie each lock's lifetime to the one
+// before it and rule out releasing them in order.
+pub struct ConcurrentList<T> {
+    head: Arc<Mutex<Node<T>>>, // sentinel; its elem is None and it is never removed
+    len: AtomicUsize,
+}
+
+struct Node<T> {
+    elem: Option<T>, // only None for the sentinel
+    next: Link<T>,
+}
+
+type Link<T> = Option<Arc<Mutex<Node<T>>>>;
+type Guard<T> = ArcMutexGuard<RawMutex, Node<T>>;
+
+impl<T: Ord> ConcurrentList<T> {
+    pub fn new() -> Self {
+        ConcurrentList { head: Arc::new(Mutex::new(Node { elem: None, next: None })), len: AtomicUsize::new(0) }
+    }
+
+    // Number of elements. With other threads modifying the list this is only a snapshot, and may
+    // already be out of date by the time it is returned.
+    pub fn len(&self) -> usize {
+        self.len.load(atomic::Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Heap memory held by the list: one Arc allocation per element plus the sentinel, each holding
+    // the reference counts and the locked node. Like len, only a snapshot while other threads are
+    // inserting or deleting.
+    pub fn memory_footprint(&self) -> Footprint {
+        Footprint::counted::<Mutex<Node<T>>>(self.len() + 1)
+    }
+
+    // Insert elem, returning false if it was already present
+    pub fn insert(&self, elem: T) -> bool {
+        let (mut prev, found) = self.find(&elem);
+        if found.is_some() {
+            return false;
+        }
+
+        let next = prev.next.take();
+        prev.next = Some(Arc::new(Mutex::new(Node { elem: Some(elem), next })));
+        self.len.fetch_add(1, atomic::Ordering::SeqCst);
+        true
+    }
+
+    // Remove elem, returning false if it was not present
+    pub fn delete(&self, elem: &T) -> bool {
+        let (mut prev, found) = self.find(elem);
+        let Some(mut cur) = found else {
+            return false;
+        };
+
+        // Both locks are held, so nobody can be between prev and cur, or about to link onto cur
+        prev.next = cur.next.take();
+        self.len.fetch_sub(1, atomic::Ordering::SeqCst);
+        true
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.find(elem).1.is_some()
+    }
+
+    // Lock the node that elem belongs after, and the node holding elem if there is one. Returns
+    // with both locks held, so the caller can relink them without anything changing in between.
+    fn find(&self, elem: &T) -> (Guard<T>, Option<Guard<T>>) {
+        let mut prev = self.head.lock_arc();
+        loop {
+            let Some(next) = prev.next.clone() else {
+                return (prev, None);
+            };
+            let cur = next.lock_arc();
+            match cur.elem.as_ref().expect("only the sentinel has no element").cmp(elem) {
+                // Taking cur before letting go of prev is what makes this hand over hand
+                Ordering::Less => prev = cur,
+                Ordering::Equal => return (prev, Some(cur)),
+                Ordering::Greater => return (prev, None),
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone> ConcurrentList<T> {
+    // Copy of the elements, in order. Taken hand over hand like everything else, so it reflects a
+    // state the list actually passed through only if nothing else is modifying it.
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut elems = Vec::new();
+        let mut cur = self.head.lock_arc();
+        while let Some(next) = cur.next.clone() {
+            cur = next.lock_arc();
+            elems.extend(cur.elem.clone());
+        }
+        elems
+    }
+}
+
+impl<T: Ord> Default for ConcurrentList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ConcurrentList<T> {
+    fn drop(&mut self) {
+        // Unlink the nodes one at a time, so dropping a long list doesn't recurse through every
+        // Arc. Nothing else can hold a reference to a node once we are being dropped.
+        let mut cur_link = self.head.lock().next.take();
+        while let Some(node) = cur_link {
+            cur_link = match Arc::try_unwrap(node) {
+                Ok(node) => node.into_inner().next.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConcurrentList;
+    use crate::workload::{Generator, KeyDist, Mix, Op};
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::sync::Arc;
+    use std::thread;
+
+    const THREADS: usize = 8;
+
+    // Inserts, deletes and lookups only, since the concurrent list has no range scans
+    fn mix() -> Mix {
+        Mix { insert: 40, delete: 40, contains: 20, range: 0 }
+    }
+
+    #[test]
+    fn basics() {
+        let list = ConcurrentList::new();
+        assert!(list.is_empty());
+        for elem in [20, 10, 30, 15] {
+            assert!(list.insert(elem));
+        }
+        assert!(!list.insert(10));
+        assert_eq!(list.to_vec(), [10, 15, 20, 30]);
+        assert_eq!(list.len(), 4);
+
+        assert!(list.delete(&15));
+        assert!(!list.delete(&15));
+        assert!(list.delete(&30));
+        assert!(list.delete(&10));
+        assert!(list.contains(&20));
+        assert!(!list.contains(&10));
+        assert_eq!(list.to_vec(), [20]);
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let list = ConcurrentList::new();
+        assert_eq!(list.memory_footprint().nodes, 1);
+
+        for elem in [20, 10, 30] {
+            list.insert(elem);
+        }
+        list.delete(&20);
+        let footprint = list.memory_footprint();
+        assert_eq!(footprint.nodes, 3);
+        assert!(footprint.bytes_per_node > 2 * std::mem::size_of::<usize>());
+        assert_eq!(footprint.heap_bytes, 3 * footprint.bytes_per_node);
+    }
+
+    #[test]
+    fn long_list_drops() {
+        let list = ConcurrentList::new();
+        for elem in (0..100_000).rev() {
+            list.insert(elem);
+        }
+        drop(list);
+    }
+
+    // Each thread runs its own workload on its own keys (those equal to its index, modulo the
+    // number of threads), so a sequential BTreeSet per thread predicts every result exactly, even
+    // though all threads share the list and keep passing through each other's nodes
+    #[test]
+    fn disjoint_keys_match_oracle() {
+        let list = Arc::new(ConcurrentList::new());
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                let mut oracle = BTreeSet::new();
+                for (op, step) in Generator::new(id as u64, 200, &mix(), KeyDist::Uniform, 1).take(5000).zip(0..) {
+                    let key = |value: i32| value * THREADS as i32 + id as i32;
+                    let (actual, expected) = match op {
+                        Op::Insert(value) => (list.insert(key(value)), oracle.insert(key(value))),
+                        Op::Delete(value) => (list.delete(&key(value)), oracle.remove(&key(value))),
+                        Op::Contains(value) => (list.contains(&key(value)), oracle.contains(&key(value))),
+                        Op::Range(..) => unreachable!("mix has no range scans"),
+                    };
+                    assert_eq!(actual, expected, "thread {} operation {} ({})", id, step, op);
+                }
+                oracle
+            })
+        }).collect();
+
+        let mut expected = BTreeSet::new();
+        for handle in handles {
+            expected.extend(handle.join().unwrap());
+        }
+        assert_eq!(list.to_vec(), expected.into_iter().collect::<Vec<_>>());
+        assert_eq!(list.len(), list.to_vec().len());
+    }
+
+    // All threads fight over the same few keys. Individual results depend on the interleaving,
+    // but for every key the successful inserts and deletes have to alternate, so inserts minus
+    // deletes is 1 if the key ended up in the list and 0 if it didn't.
+    #[test]
+    fn shared_keys_balance() {
+        let list = Arc::new(ConcurrentList::new());
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                let mut net = BTreeMap::<i32, i64>::new();
+                for op in Generator::new(100 + id as u64, 32, &mix(), KeyDist::Zipf(1.0), 1).take(20_000) {
+                    match op {
+                        Op::Insert(value) if list.insert(value) => *net.entry(value).or_default() += 1,
+                        Op::Delete(value) if list.delete(&value) => *net.entry(value).or_default() -= 1,
+                        Op::Contains(value) => {
+                            list.contains(&value);
+                        }
+                        _ => {}
+                    }
+                }
+                net
+            })
+        }).collect();
+
+        let mut net = BTreeMap::<i32, i64>::new();
+        for handle in handles {
+            for (value, count) in handle.join().unwrap() {
+                *net.entry(value).or_default() += count;
+            }
+        }
+
+        let contents = list.to_vec();
+        for value in 1..=32 {
+            let expected = if contents.contains(&value) { 1 } else { 0 };
+            assert_eq!(net.get(&value).copied().unwrap_or(0), expected, "value {}", value);
+        }
+        assert!(contents.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(list.len(), contents.len());
+    }
+}
@@ -1,55 +1,283 @@
-use std::mem;
+use std::alloc::{self, Layout};
+use std::error;
+use std::fmt::{self, Debug, Display};
+use std::ops::{Bound, RangeBounds};
 
-struct Node {
-    elem: i32, // simple list that only stores integers
-    next: Option<Box<Node>>,
+use crate::dot;
+use crate::footprint::Footprint;
+use crate::sorted_set::{after_end, before_start, SortedSet};
+
+// Ways that inserting into or deleting from the list can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Duplicate,                          // element to insert is already in the list
+    NotFound,                           // element to delete is not in the list
+    CapacityExceeded { max_len: usize }, // list already holds its configured maximum
+    AllocFailed,                        // allocator could not provide memory for a new node
+}
+
+// A broken invariant, as found by List::check_invariants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantError {
+    NotIncreasing { index: usize },           // element at index is not greater than the one before it
+    LenMismatch { len: usize, nodes: usize }, // len doesn't match the number of nodes
+}
+
+pub struct IntoIter<T>(List<T>); // consume list, yielding elements in sorted order
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+// Elements within a range. The lower bound is skipped past when the iterator is created; the upper
+// bound is checked as we go so that iteration stops at the first element beyond it.
+pub struct Range<'a, T, R> {
+    iter: Iter<'a, T>,
+    bounds: R,
 }
 
-pub struct List {
-    head: Option<Box<Node>>,
-    len: u32,
+struct Node<T> {
+    elem: T, // any totally ordered type; the list keeps these sorted and unique
+    next: Option<Box<Node<T>>>,
 }
 
-impl List {
+pub struct List<T> {
+    head: Option<Box<Node<T>>>,
+    len: usize,
+    max_len: Option<usize>, // None means no limit
+}
+
+impl<T: Ord> List<T> {
     pub fn new() -> Self {
-        List { head: None, len: 0 } // return empty list
+        List { head: None, len: 0, max_len: None } // return empty list
     }
 
-    // Print the contents of the list
-    pub fn print(&mut self) {
-        println!("length: {}", self.len);
+    // Empty list that refuses to grow beyond max_len elements
+    pub fn with_max_len(max_len: usize) -> Self {
+        List { head: None, len: 0, max_len: Some(max_len) }
+    }
 
-        let mut cur_node = &self.head;
-        while let Some(node) = cur_node {
-            print!("{} -> ", node.elem);
-            cur_node = &node.next;
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    // Change (or remove) the limit. Lowering it below the current length doesn't remove anything;
+    // it just stops further inserts until enough elements have been deleted.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.max_len = max_len;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // Heap memory held by the list: one node allocation per element
+    pub fn memory_footprint(&self) -> Footprint {
+        Footprint::boxed::<Node<T>>(self.len)
+    }
+
+    // Verify what every other method relies on: elements strictly increase from head to tail (so
+    // there are no duplicates), and len is the number of nodes. Walks the whole list.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        let mut nodes = 0;
+        let mut prev: Option<&T> = None;
+        for cur in self.iter() {
+            if prev.is_some_and(|prev| prev >= cur) {
+                return Err(InvariantError::NotIncreasing { index: nodes });
+            }
+            prev = Some(cur);
+            nodes += 1;
+        }
+
+        if nodes != self.len {
+            return Err(InvariantError::LenMismatch { len: self.len, nodes });
+        }
+        Ok(())
+    }
+
+    // Called at the end of every operation that changes the list. With the "check-invariants"
+    // feature it panics as soon as one leaves the list broken; otherwise it does nothing.
+    #[inline]
+    fn invariants_hold(&self) {
+        #[cfg(feature = "check-invariants")]
+        if let Err(err) = self.check_invariants() {
+            panic!("cs120::List invariant broken: {}", err);
+        }
+    }
+
+    // Smallest element in the list
+    pub fn first(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    // Largest element in the list (requires walking the whole list)
+    pub fn last(&self) -> Option<&T> {
+        self.iter().last()
+    }
+
+    // Check whether elem is in the list. Since the list is sorted, we can stop as soon as we reach
+    // a value greater than elem
+    pub fn contains(&self, elem: &T) -> bool {
+        for cur in self.iter() {
+            if cur == elem {
+                return true;
+            } else if cur > elem {
+                return false;
+            }
+        }
+        false
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head.as_deref() }
+    }
+
+    // Iterate over the elements within range, in sorted order. Accepts any range form (lo..hi,
+    // lo..=hi, lo.., ..hi, .., or a pair of Bounds). A range whose start lies after its end is
+    // simply empty.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        // Skip the elements that come before the start of the range
+        let mut next = self.head.as_deref();
+        while let Some(node) = next {
+            if !before_start(range.start_bound(), &node.elem) {
+                break;
+            }
+            next = node.next.as_deref();
+        }
+
+        Range { iter: Iter { next }, bounds: range }
+    }
+
+    pub fn count_in_range<R: RangeBounds<T>>(&self, range: R) -> usize {
+        self.range(range).count()
+    }
+
+    // Largest element less than or equal to elem
+    pub fn floor(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Unbounded, Bound::Included(elem))).last()
+    }
+
+    // Smallest element greater than or equal to elem
+    pub fn ceiling(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Included(elem), Bound::Unbounded)).next()
+    }
+
+    // Largest element strictly less than elem
+    pub fn predecessor(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Unbounded, Bound::Excluded(elem))).last()
+    }
+
+    // Smallest element strictly greater than elem
+    pub fn successor(&self, elem: &T) -> Option<&T> {
+        self.range((Bound::Excluded(elem), Bound::Unbounded)).next()
+    }
+
+    // Mutable counterpart to iter(). Handing out &mut T from an iterator would let callers break
+    // the ordering, so instead we apply f to every element (in order) and then restore the
+    // invariant: if any element moved out of place the nodes are re-sorted, and if two elements
+    // became equal only the first one is kept.
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut cur_opt = &mut self.head;
+        while let Some(cur_node) = cur_opt {
+            f(&mut cur_node.elem);
+            cur_opt = &mut cur_node.next;
+        }
+
+        let mut prev: Option<&T> = None;
+        let mut still_sorted = true;
+        for cur in self.iter() {
+            if prev.is_some_and(|prev| prev >= cur) {
+                still_sorted = false;
+                break;
+            }
+            prev = Some(cur);
+        }
+
+        if !still_sorted {
+            self.resort();
+        }
+        self.invariants_hold();
+    }
+
+    // Re-establish the sorted, duplicate-free invariant after elements were modified in place.
+    // The existing nodes are detached, sorted (stably, so the first of several equal elements
+    // wins), and linked back together.
+    fn resort(&mut self) {
+        let mut nodes = Vec::with_capacity(self.len);
+        let mut cur_node = self.head.take();
+        while let Some(mut node) = cur_node {
+            cur_node = node.next.take();
+            nodes.push(node);
+        }
+
+        nodes.sort_by(|a, b| a.elem.cmp(&b.elem));
+        nodes.dedup_by(|later, earlier| later.elem == earlier.elem);
+        self.len = nodes.len();
+
+        while let Some(mut node) = nodes.pop() {
+            node.next = self.head.take();
+            self.head = Some(node);
+        }
+    }
+
+    // Insert an element into the repository, returning true if it was inserted and false if it
+    // was not (because it was already present or the list is full). Panics if memory for the new
+    // node can't be allocated, the same as Box::new would; use try_insert to handle that instead.
+    pub fn insert(&mut self, elem: T) -> bool {
+        match self.try_insert(elem) {
+            Ok(()) => true,
+            Err(Error::Duplicate) | Err(Error::CapacityExceeded { .. }) => false,
+            Err(Error::NotFound) => unreachable!(),
+            Err(Error::AllocFailed) => alloc::handle_alloc_error(Layout::new::<Node<T>>()),
         }
-        println!("end");
     }
 
-    // Insert a number into the repository. Each number should only appear at most once in the
-    // repository. The operation should return True if the number was inserted, False if the number
-    // was not inserted, and an Error if the operation had a problem completing correctly.
-    pub fn insert(&mut self, elem: i32) -> bool {
+    // Insert an element into the repository. Each element should only appear at most once in the
+    // repository. The operation returns Ok if the element was inserted, Error::Duplicate if the
+    // element was not inserted because it is already present, and another Error if the operation
+    // had a problem completing correctly (the list is at its maximum length, or a node could not
+    // be allocated). The list is unchanged whenever an Error is returned.
+    pub fn try_insert(&mut self, elem: T) -> Result<(), Error> {
+        let result = self.insert_node(elem);
+        self.invariants_hold();
+        result
+    }
+
+    fn insert_node(&mut self, elem: T) -> Result<(), Error> {
+        // Full list: nothing can be inserted, but still report duplicates as such
+        if let Some(max_len) = self.max_len {
+            if self.len >= max_len {
+                if self.contains(&elem) {
+                    return Err(Error::Duplicate);
+                }
+                return Err(Error::CapacityExceeded { max_len });
+            }
+        }
+
         // Empty list, just add as only element
         if self.head.is_none() {
-            let new_node = Box::new(Node { elem: elem, next: None });
+            let new_node = alloc_node(elem)?;
             self.head = Some(new_node);
             self.len += 1;
-            return true;
+            return Ok(());
         }
 
         // Special case to allow checking head itself
         if let Some(node) = &mut self.head {
             if elem == node.elem {
-                return false;
+                return Err(Error::Duplicate);
             } else if elem < node.elem {
                 // Value to insert is less than head; add new node before current head (and set
-                // head to new node)
-                let new_node = Box::new(Node { elem: elem, next: mem::replace(&mut self.head, None) });
+                // head to new node). Allocate first so that a failure leaves the list untouched.
+                let mut new_node = alloc_node(elem)?;
+                new_node.next = self.head.take();
                 self.head = Some(new_node);
                 self.len += 1;
-                return true;
+                return Ok(());
             }
         }
 
@@ -59,44 +287,56 @@ impl List {
         while let Some(ref mut cur_node) = cur_opt { // TODO: understand "ref" in more detail
             if let Some(next_node) = &cur_node.next {
                 if elem == next_node.elem {
-                    return false;
+                    return Err(Error::Duplicate);
                 } else if elem < next_node.elem {
                     // Value to insert is less than next nodes's value; add new node between
                     // cur_node and next_node
-                    let new_node = Box::new(Node { elem: elem, next: mem::replace(&mut cur_node.next, None) });
+                    let mut new_node = alloc_node(elem)?;
+                    new_node.next = cur_node.next.take();
                     cur_node.next = Some(new_node);
                     self.len += 1;
-                    return true;
+                    return Ok(());
                 }
                 cur_opt = &mut cur_node.next;
             } else { // next_node is null
                 // We traversed the whole list and this value was greater than every element. Add at end.
-                let new_node = Box::new(Node { elem: elem, next: None });
+                let new_node = alloc_node(elem)?;
                 cur_node.next = Some(new_node);
                 self.len += 1;
-                return true;
+                return Ok(());
             }
         }
 
         unreachable!();
     }
 
-    // Remove a number from the repository if such exists. The operation should return True if the
-    // number was removed, and False if the number was not found in the repository.
-    pub fn delete(&mut self, elem: i32) -> bool {
+    // Remove an element from the repository if such exists. The operation returns true if the
+    // element was removed, and false if the element was not found in the repository.
+    pub fn delete(&mut self, elem: &T) -> bool {
+        self.try_delete(elem).is_ok()
+    }
+
+    // Same as delete, but reports a missing element as Error::NotFound
+    pub fn try_delete(&mut self, elem: &T) -> Result<(), Error> {
+        let result = self.delete_node(elem);
+        self.invariants_hold();
+        result
+    }
+
+    fn delete_node(&mut self, elem: &T) -> Result<(), Error> {
         // Empty list, nothing to do
         if self.head.is_none() {
-            return false;
+            return Err(Error::NotFound);
         }
 
         // Special case to allow checking head itself
         if let Some(node) = &mut self.head {
-            if elem == node.elem {
-                self.head = mem::replace(&mut node.next, None);
+            if *elem == node.elem {
+                self.head = node.next.take();
                 self.len -= 1;
-                return true;
-            } else if elem < node.elem {
-                return false;
+                return Ok(());
+            } else if *elem < node.elem {
+                return Err(Error::NotFound);
             }
         }
         // At this point, we know value to remove is greater than the value of the head
@@ -106,17 +346,17 @@ impl List {
         let mut cur_opt = &mut self.head;
         while let Some(ref mut cur_node) = cur_opt { // TODO: understand "ref" in more detail
             if let Some(next_node) = &mut cur_node.next {
-                if elem == next_node.elem {
-                    cur_node.next = mem::replace(&mut next_node.next, None);
+                if *elem == next_node.elem {
+                    cur_node.next = next_node.next.take();
                     self.len -= 1;
-                    return true;
-                } else if elem < next_node.elem {
-                    return false;
+                    return Ok(());
+                } else if *elem < next_node.elem {
+                    return Err(Error::NotFound);
                 }
                 cur_opt = &mut cur_node.next;
             } else { // next_node is null
                 // We traversed the whole list and this value was greater than every element. Nothing to do
-                return false;
+                return Err(Error::NotFound);
             }
         }
 
@@ -124,20 +364,446 @@ impl List {
     }
 }
 
-impl Drop for List {
+// Box::new aborts the process if the allocator fails. This is the fallible equivalent (in the
+// spirit of Vec::try_reserve), so that running out of memory can be reported as an Error.
+fn alloc_node<T>(elem: T) -> Result<Box<Node<T>>, Error> {
+    let layout = Layout::new::<Node<T>>(); // never zero-sized, since it holds a pointer
+    // SAFETY: layout has a non-zero size
+    let ptr = unsafe { alloc::alloc(layout) } as *mut Node<T>;
+    if ptr.is_null() {
+        return Err(Error::AllocFailed);
+    }
+    // SAFETY: ptr is non-null and was allocated by the global allocator with the layout of
+    // Node<T>, which is exactly what Box expects to own
+    unsafe {
+        ptr.write(Node { elem, next: None });
+        Ok(Box::from_raw(ptr))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Duplicate => write!(f, "element is already in the list"),
+            Error::NotFound => write!(f, "element is not in the list"),
+            Error::CapacityExceeded { max_len } => {
+                write!(f, "list is already at its maximum length of {}", max_len)
+            }
+            Error::AllocFailed => write!(f, "failed to allocate a new list node"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvariantError::NotIncreasing { index } => {
+                write!(f, "element {} is not greater than the element before it", index)
+            }
+            InvariantError::LenMismatch { len, nodes } => {
+                write!(f, "len is {} but the list has {} nodes", len, nodes)
+            }
+        }
+    }
+}
+
+impl error::Error for InvariantError {}
+
+// Which elements survive a merge of two lists: those only in the left list, those only in the
+// right list, and those in both. Shared with arena::SortedList.
+pub(crate) struct Keep {
+    pub(crate) left: bool,
+    pub(crate) right: bool,
+    pub(crate) both: bool,
+}
+
+pub(crate) const UNION: Keep = Keep { left: true, right: true, both: true };
+pub(crate) const INTERSECTION: Keep = Keep { left: false, right: false, both: true };
+pub(crate) const DIFFERENCE: Keep = Keep { left: true, right: false, both: false };
+pub(crate) const SYMMETRIC_DIFFERENCE: Keep = Keep { left: true, right: true, both: false };
+
+// Set algebra. Since both lists are sorted, each operation is a single linear merge. The into_*
+// variants consume both lists and relink their existing nodes into the result (nodes that don't
+// make it into the result are freed); the borrowing variants leave both lists alone and clone the
+// surviving elements into a new list.
+impl<T: Ord> List<T> {
+    pub fn into_union(self, other: List<T>) -> List<T> {
+        self.merge(other, UNION)
+    }
+
+    pub fn into_intersection(self, other: List<T>) -> List<T> {
+        self.merge(other, INTERSECTION)
+    }
+
+    pub fn into_difference(self, other: List<T>) -> List<T> {
+        self.merge(other, DIFFERENCE)
+    }
+
+    pub fn into_symmetric_difference(self, other: List<T>) -> List<T> {
+        self.merge(other, SYMMETRIC_DIFFERENCE)
+    }
+
+    // True if every element of self is also in other
+    pub fn is_subset(&self, other: &List<T>) -> bool {
+        let mut other_iter = other.iter().peekable();
+        for elem in self.iter() {
+            // Skip past the smaller elements of other; the next one has to be a match
+            while other_iter.next_if(|other_elem| *other_elem < elem).is_some() {}
+            if other_iter.next_if_eq(&elem).is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
+    // True if self and other have no elements in common
+    pub fn is_disjoint(&self, other: &List<T>) -> bool {
+        let mut iter = self.iter().peekable();
+        let mut other_iter = other.iter().peekable();
+        while let (Some(elem), Some(other_elem)) = (iter.peek(), other_iter.peek()) {
+            if elem < other_elem {
+                iter.next();
+            } else if elem > other_elem {
+                other_iter.next();
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn merge(mut self, mut other: List<T>, keep: Keep) -> List<T> {
+        let mut left = self.head.take();
+        let mut right = other.head.take();
+
+        let mut result = List::new();
+        let mut len = 0;
+        let mut tail = &mut result.head;
+
+        // Detach the smaller of the two front nodes (or both, if they are equal) and either link
+        // it onto the end of the result or let it drop
+        while let (Some(left_node), Some(right_node)) = (&left, &right) {
+            let (node, kept) = if left_node.elem < right_node.elem {
+                (take_node(&mut left), keep.left)
+            } else if left_node.elem > right_node.elem {
+                (take_node(&mut right), keep.right)
+            } else {
+                take_node(&mut right);
+                (take_node(&mut left), keep.both)
+            };
+
+            if kept {
+                tail = &mut tail.insert(node).next;
+                len += 1;
+            }
+        }
+
+        // Whatever is left over in either list is larger than everything linked so far
+        let (rest, kept) = if left.is_some() { (left, keep.left) } else { (right, keep.right) };
+        if kept {
+            *tail = rest;
+            len += result.iter().skip(len).count();
+        } else {
+            // Hand the leftover nodes to a list so they are freed without recursion
+            drop(List { head: rest, len: 0, max_len: None });
+        }
+
+        result.len = len;
+        result.invariants_hold();
+        result
+    }
+}
+
+impl<T: Ord + Clone> List<T> {
+    pub fn union(&self, other: &List<T>) -> List<T> {
+        self.merge_cloned(other, UNION)
+    }
+
+    pub fn intersection(&self, other: &List<T>) -> List<T> {
+        self.merge_cloned(other, INTERSECTION)
+    }
+
+    pub fn difference(&self, other: &List<T>) -> List<T> {
+        self.merge_cloned(other, DIFFERENCE)
+    }
+
+    pub fn symmetric_difference(&self, other: &List<T>) -> List<T> {
+        self.merge_cloned(other, SYMMETRIC_DIFFERENCE)
+    }
+
+    fn merge_cloned(&self, other: &List<T>, keep: Keep) -> List<T> {
+        let mut left = self.iter().peekable();
+        let mut right = other.iter().peekable();
+
+        let mut result = List::new();
+        let mut len = 0;
+        let mut tail = &mut result.head;
+
+        loop {
+            let (elem, kept) = match (left.peek(), right.peek()) {
+                (Some(left_elem), Some(right_elem)) if left_elem < right_elem => {
+                    (left.next(), keep.left)
+                }
+                (Some(left_elem), Some(right_elem)) if left_elem > right_elem => {
+                    (right.next(), keep.right)
+                }
+                (Some(_), Some(_)) => {
+                    right.next();
+                    (left.next(), keep.both)
+                }
+                (Some(_), None) => (left.next(), keep.left),
+                (None, Some(_)) => (right.next(), keep.right),
+                (None, None) => break,
+            };
+
+            if let (Some(elem), true) = (elem, kept) {
+                let new_node = Box::new(Node { elem: elem.clone(), next: None });
+                tail = &mut tail.insert(new_node).next;
+                len += 1;
+            }
+        }
+
+        result.len = len;
+        result.invariants_hold();
+        result
+    }
+}
+
+// Detach the first node of a chain, leaving the rest of the chain in its place
+fn take_node<T>(link: &mut Option<Box<Node<T>>>) -> Box<Node<T>> {
+    let mut node = link.take().expect("take_node called on an empty link");
+    *link = node.next.take();
+    node
+}
+
+impl<T: Ord + Display> List<T> {
+    // Print the length and contents of the list
+    pub fn print(&self) {
+        println!("length: {}", self.len);
+        println!("{}", self);
+    }
+
+    // The list's nodes and next pointers as a Graphviz DOT graph (see dot::render)
+    pub fn to_dot(&self) -> String {
+        dot::render("cs120::List", self.iter())
+    }
+}
+
+// Elements in order, as `a -> b -> end`
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            write!(f, "{} -> ", node.elem)?;
+            cur_node = &node.next;
+        }
+        write!(f, "end")
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            list.entry(&node.elem);
+            cur_node = &node.next;
+        }
+        list.finish()
+    }
+}
+
+// Serialized as a sequence of elements in increasing order. max_len is configuration rather than
+// contents, so it is not included, and a deserialized list has no maximum length.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            seq.serialize_element(&node.elem)?;
+            cur_node = &node.next;
+        }
+        seq.end()
+    }
+}
+
+// Rejects any sequence that is not strictly increasing, since that can't have come from a List
+#[cfg(feature = "serde")]
+impl<'de, T: Ord + serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{self, SeqAccess, Visitor};
+        use std::marker::PhantomData;
+
+        struct ListVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Ord + serde::Deserialize<'de>> Visitor<'de> for ListVisitor<T> {
+            type Value = List<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a sequence of unique elements in increasing order")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<List<T>, A::Error> {
+                let mut elems: Vec<T> = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(elem) = seq.next_element()? {
+                    if let Some(last) = elems.last() {
+                        if elem <= *last {
+                            let problem = if elem == *last { "is a duplicate" } else { "is out of order" };
+                            return Err(de::Error::custom(format_args!(
+                                "element {} {}: a cs120::List must be sorted with no duplicates",
+                                elems.len(), problem
+                            )));
+                        }
+                    }
+                    elems.push(elem);
+                }
+
+                // Link the nodes up from the back, so each one goes on the front
+                let mut list = List::new();
+                list.len = elems.len();
+                while let Some(elem) = elems.pop() {
+                    list.head = Some(Box::new(Node { elem, next: list.head.take() }));
+                }
+                Ok(list)
+            }
+        }
+
+        deserializer.deserialize_seq(ListVisitor(PhantomData))
+    }
+}
+
+impl<T: Ord> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Ord> SortedSet<T> for List<T> {
+    type Iter<'a> = Iter<'a, T> where T: 'a;
+    type Range<'a, R> = Range<'a, T, R> where T: 'a, R: RangeBounds<T>;
+
+    fn insert(&mut self, elem: T) -> bool {
+        List::insert(self, elem)
+    }
+
+    fn delete(&mut self, elem: &T) -> bool {
+        List::delete(self, elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        List::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        List::iter(self)
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T, R> {
+        List::range(self, range)
+    }
+}
+
+impl<T> Drop for List<T> {
     fn drop(&mut self) {
         //println!("!!! dropping !!!");
-        let mut cur_node = mem::replace(&mut self.head, None);
+        let mut cur_node = self.head.take();
         while let Some(mut node) = cur_node {
             //println!("dropping node containing {}", node.elem);
-            cur_node = mem::replace(&mut node.next, None);
+            cur_node = node.next.take();
         }
     }
 }
 
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Elements come off the front, which is where the smallest one lives
+        self.0.head.take().map(|node| {
+            self.0.head = node.next;
+            self.0.len -= 1;
+            node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Range<'a, T, R> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elem = self.iter.next()?;
+        if after_end(self.bounds.end_bound(), elem) {
+            // Everything after this is larger still, so we are done
+            self.iter.next = None;
+            return None;
+        }
+        Some(elem)
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod test{
-    use super::List;
+    use super::{Error, InvariantError, List};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::cell::Cell;
+    use std::collections::BTreeSet;
+    use std::cmp::Ordering;
+    use std::ops::Bound;
+
+    // Walk the nodes directly so the tests can check ordering without relying on print()
+    fn contents<T: Ord + Clone>(list: &List<T>) -> Vec<T> {
+        let mut elems = Vec::new();
+        let mut cur_node = &list.head;
+        while let Some(node) = cur_node {
+            elems.push(node.elem.clone());
+            cur_node = &node.next;
+        }
+        elems
+    }
 
     #[test]
     fn basics() {
@@ -177,35 +843,769 @@ mod test{
         list.print();
 
         // Deleting from beginning and end should work
-        assert_eq!(list.delete(7), true);
-        assert_eq!(list.delete(22), true);
+        assert_eq!(list.delete(&7), true);
+        assert_eq!(list.delete(&22), true);
 
         // Deleting from middle should work
-        assert_eq!(list.delete(11), true);
-        assert_eq!(list.delete(12), true);
+        assert_eq!(list.delete(&11), true);
+        assert_eq!(list.delete(&12), true);
 
         // Deleting already deleted elements should fail
-        assert_eq!(list.delete(7), false);
-        assert_eq!(list.delete(22), false);
-        assert_eq!(list.delete(12), false);
+        assert_eq!(list.delete(&7), false);
+        assert_eq!(list.delete(&22), false);
+        assert_eq!(list.delete(&12), false);
 
         list.print();
 
         let mut list2 = List::new();
 
         // Deleting from empty list should fail
-        assert_eq!(list2.delete(1), false);
+        assert_eq!(list2.delete(&1), false);
 
         assert_eq!(list2.insert(1), true);
 
         // Deleting only element from list should work
-        assert_eq!(list2.delete(1), true);
+        assert_eq!(list2.delete(&1), true);
 
         assert_eq!(list2.insert(1), true);
         assert_eq!(list2.insert(2), true);
-        assert_eq!(list2.delete(1), true);
-        assert_eq!(list2.delete(1), false);
+        assert_eq!(list2.delete(&1), true);
+        assert_eq!(list2.delete(&1), false);
 
         list2.print();
     }
+
+    #[test]
+    fn strings() {
+        let mut list = List::new();
+
+        assert_eq!(list.insert(String::from("pear")), true);
+        assert_eq!(list.insert(String::from("apple")), true);
+        assert_eq!(list.insert(String::from("zucchini")), true);
+        assert_eq!(list.insert(String::from("fig")), true);
+
+        // Duplicates are rejected no matter where they fall in the list
+        assert_eq!(list.insert(String::from("apple")), false);
+        assert_eq!(list.insert(String::from("fig")), false);
+        assert_eq!(list.insert(String::from("zucchini")), false);
+
+        assert_eq!(contents(&list), ["apple", "fig", "pear", "zucchini"]);
+        assert_eq!(list.len, 4);
+
+        assert_eq!(list.delete(&String::from("fig")), true);
+        assert_eq!(list.delete(&String::from("fig")), false);
+        assert_eq!(list.delete(&String::from("banana")), false);
+        assert_eq!(list.delete(&String::from("zzz")), false);
+
+        assert_eq!(contents(&list), ["apple", "pear", "zucchini"]);
+        assert_eq!(list.len, 3);
+
+        list.print();
+    }
+
+    #[test]
+    fn tuples() {
+        let mut list = List::new();
+
+        // Tuples are ordered lexicographically, so the second field breaks ties in the first
+        assert_eq!(list.insert((2, 'b')), true);
+        assert_eq!(list.insert((1, 'z')), true);
+        assert_eq!(list.insert((2, 'a')), true);
+        assert_eq!(list.insert((1, 'z')), false);
+
+        assert_eq!(contents(&list), [(1, 'z'), (2, 'a'), (2, 'b')]);
+
+        assert_eq!(list.delete(&(2, 'a')), true);
+        assert_eq!(list.delete(&(2, 'c')), false);
+        assert_eq!(contents(&list), [(1, 'z'), (2, 'b')]);
+        assert_eq!(list.len, 2);
+    }
+
+    // Struct that is ordered (and considered equal) by its key alone
+    #[derive(Clone, Debug)]
+    struct Account {
+        id: u32,
+        owner: &'static str,
+    }
+
+    impl PartialEq for Account {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Eq for Account {}
+
+    impl PartialOrd for Account {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Account {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.id.cmp(&other.id)
+        }
+    }
+
+    #[test]
+    fn keyed_structs() {
+        let mut list = List::new();
+
+        assert_eq!(list.insert(Account { id: 30, owner: "carol" }), true);
+        assert_eq!(list.insert(Account { id: 10, owner: "alice" }), true);
+        assert_eq!(list.insert(Account { id: 20, owner: "bob" }), true);
+
+        // Same key with a different payload counts as a duplicate, and the original is kept
+        assert_eq!(list.insert(Account { id: 20, owner: "mallory" }), false);
+
+        let owners: Vec<_> = contents(&list).iter().map(|account| account.owner).collect();
+        assert_eq!(owners, ["alice", "bob", "carol"]);
+
+        // Deleting only needs the key to match
+        assert_eq!(list.delete(&Account { id: 10, owner: "" }), true);
+        assert_eq!(list.delete(&Account { id: 10, owner: "alice" }), false);
+        assert_eq!(list.len, 2);
+    }
+
+    #[test]
+    fn lookups() {
+        let mut list = List::new();
+
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.is_empty(), true);
+        assert_eq!(list.first(), None);
+        assert_eq!(list.last(), None);
+        assert_eq!(list.contains(&1), false);
+
+        list.insert(20);
+        list.insert(10);
+        list.insert(30);
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.is_empty(), false);
+        assert_eq!(list.first(), Some(&10));
+        assert_eq!(list.last(), Some(&30));
+
+        // Values at the head, middle and tail should be found
+        assert_eq!(list.contains(&10), true);
+        assert_eq!(list.contains(&20), true);
+        assert_eq!(list.contains(&30), true);
+
+        // Values before, between and after the stored values should not
+        assert_eq!(list.contains(&5), false);
+        assert_eq!(list.contains(&25), false);
+        assert_eq!(list.contains(&35), false);
+
+        list.delete(&10);
+        list.delete(&30);
+        assert_eq!(list.first(), Some(&20));
+        assert_eq!(list.last(), Some(&20));
+
+        list.delete(&20);
+        assert_eq!(list.is_empty(), true);
+    }
+
+    #[test]
+    fn iter() {
+        let mut list = List::new();
+        list.insert(3); list.insert(1); list.insert(2);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next(), None);
+
+        // Borrowing for loops go through IntoIterator for &List
+        let mut sum = 0;
+        for elem in &list {
+            sum += elem;
+        }
+        assert_eq!(sum, 6);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut list = List::new();
+        list.insert(3); list.insert(1); list.insert(2);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn for_each_mut() {
+        let mut list = List::new();
+        for elem in [5, 1, 4, 2, 3] {
+            list.insert(elem);
+        }
+
+        // Order-preserving updates leave the list as is
+        list.for_each_mut(|elem| *elem *= 10);
+        assert_eq!(contents(&list), [10, 20, 30, 40, 50]);
+
+        // Updates that reverse the order get re-sorted
+        list.for_each_mut(|elem| *elem = -*elem);
+        assert_eq!(contents(&list), [-50, -40, -30, -20, -10]);
+        assert_eq!(list.len(), 5);
+
+        // Updates that produce equal elements keep only one of them
+        list.for_each_mut(|elem| *elem /= 20);
+        assert_eq!(contents(&list), [-2, -1, 0]);
+        assert_eq!(list.len(), 3);
+
+        // The list still works normally afterwards
+        assert_eq!(list.insert(-3), true);
+        assert_eq!(list.insert(0), false);
+        assert_eq!(list.delete(&-1), true);
+        assert_eq!(contents(&list), [-3, -2, 0]);
+    }
+
+    #[test]
+    fn for_each_mut_keeps_first_duplicate() {
+        let mut list = List::new();
+        list.insert(Account { id: 1, owner: "alice" });
+        list.insert(Account { id: 2, owner: "bob" });
+        list.insert(Account { id: 3, owner: "carol" });
+
+        // Collapse everything onto the same key; the earliest element in list order survives
+        list.for_each_mut(|account| account.id = 7);
+        let remaining = contents(&list);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].owner, "alice");
+        assert_eq!(list.len(), 1);
+    }
+
+    fn range_list() -> List<i32> {
+        let mut list = List::new();
+        for elem in [10, 20, 30, 40, 50] {
+            list.insert(elem);
+        }
+        list
+    }
+
+    #[test]
+    fn range_bounds() {
+        let list = range_list();
+
+        // Exclusive and inclusive ends
+        assert_eq!(list.range(20..40).copied().collect::<Vec<_>>(), [20, 30]);
+        assert_eq!(list.range(20..=40).copied().collect::<Vec<_>>(), [20, 30, 40]);
+
+        // Bounds that fall between stored values
+        assert_eq!(list.range(15..45).copied().collect::<Vec<_>>(), [20, 30, 40]);
+        assert_eq!(list.range(15..=45).copied().collect::<Vec<_>>(), [20, 30, 40]);
+
+        // Half-open and unbounded forms
+        assert_eq!(list.range(30..).copied().collect::<Vec<_>>(), [30, 40, 50]);
+        assert_eq!(list.range(..30).copied().collect::<Vec<_>>(), [10, 20]);
+        assert_eq!(list.range(..=30).copied().collect::<Vec<_>>(), [10, 20, 30]);
+        assert_eq!(list.range(..).copied().collect::<Vec<_>>(), [10, 20, 30, 40, 50]);
+
+        // Explicit Bounds, including an excluded start
+        let excluded = (Bound::Excluded(20), Bound::Included(40));
+        assert_eq!(list.range(excluded).copied().collect::<Vec<_>>(), [30, 40]);
+        let excluded = (Bound::Excluded(20), Bound::Excluded(40));
+        assert_eq!(list.range(excluded).copied().collect::<Vec<_>>(), [30]);
+    }
+
+    #[test]
+    fn range_empty() {
+        let list = range_list();
+
+        // Empty and inverted ranges
+        assert_eq!(list.range(30..30).next(), None);
+        assert_eq!(list.range(31..40).next(), None);
+        assert_eq!(list.range((Bound::Included(40), Bound::Excluded(20))).next(), None);
+        let excluded = (Bound::Excluded(30), Bound::Excluded(30));
+        assert_eq!(list.range(excluded).next(), None);
+
+        // Ranges entirely before or after the stored values
+        assert_eq!(list.range(..10).next(), None);
+        assert_eq!(list.range(0..5).next(), None);
+        assert_eq!(list.range(51..).next(), None);
+        assert_eq!(list.range(60..=70).next(), None);
+
+        // Ranges that cover more than the stored values
+        assert_eq!(list.range(0..100).count(), 5);
+
+        // Empty list
+        let empty: List<i32> = List::new();
+        assert_eq!(empty.range(..).next(), None);
+        assert_eq!(empty.count_in_range(0..10), 0);
+    }
+
+    #[test]
+    fn count_in_range() {
+        let list = range_list();
+
+        assert_eq!(list.count_in_range(..), 5);
+        assert_eq!(list.count_in_range(20..=40), 3);
+        assert_eq!(list.count_in_range(20..40), 2);
+        assert_eq!(list.count_in_range(21..30), 0);
+        assert_eq!(list.count_in_range(100..), 0);
+    }
+
+    #[test]
+    fn neighbours() {
+        let list = range_list();
+
+        // Lookups on stored values
+        assert_eq!(list.floor(&30), Some(&30));
+        assert_eq!(list.ceiling(&30), Some(&30));
+        assert_eq!(list.predecessor(&30), Some(&20));
+        assert_eq!(list.successor(&30), Some(&40));
+
+        // Lookups between stored values
+        assert_eq!(list.floor(&35), Some(&30));
+        assert_eq!(list.ceiling(&35), Some(&40));
+        assert_eq!(list.predecessor(&35), Some(&30));
+        assert_eq!(list.successor(&35), Some(&40));
+
+        // Lookups at and past either end
+        assert_eq!(list.floor(&5), None);
+        assert_eq!(list.ceiling(&5), Some(&10));
+        assert_eq!(list.predecessor(&10), None);
+        assert_eq!(list.successor(&10), Some(&20));
+        assert_eq!(list.floor(&55), Some(&50));
+        assert_eq!(list.ceiling(&55), None);
+        assert_eq!(list.predecessor(&50), Some(&40));
+        assert_eq!(list.successor(&50), None);
+
+        let empty: List<i32> = List::new();
+        assert_eq!(empty.floor(&1), None);
+        assert_eq!(empty.successor(&1), None);
+    }
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Integer wrapper that counts how many times it gets compared
+    #[derive(PartialEq, Eq, Debug)]
+    struct Counted(i32);
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Counted {
+        fn cmp(&self, other: &Self) -> Ordering {
+            COMPARISONS.with(|count| count.set(count.get() + 1));
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn range_stops_early() {
+        let mut list = List::new();
+        for elem in (1..=1000).rev() {
+            list.insert(Counted(elem));
+        }
+
+        // A range near the front should not look at the rest of the list
+        COMPARISONS.with(|count| count.set(0));
+        assert_eq!(list.count_in_range(Counted(2)..Counted(5)), 3);
+        assert_eq!(list.successor(&Counted(3)), Some(&Counted(4)));
+        assert!(COMPARISONS.with(|count| count.get()) < 20);
+    }
+
+    // Random list along with a BTreeSet holding the same elements
+    fn random_set(rng: &mut StdRng, max_len: usize, value_range: i32) -> (List<i32>, BTreeSet<i32>) {
+        let mut list = List::new();
+        let mut set = BTreeSet::new();
+        for _ in 0..rng.gen_range(0..=max_len) {
+            let value = rng.gen_range(0..value_range);
+            assert_eq!(list.insert(value), set.insert(value));
+        }
+        (list, set)
+    }
+
+    fn check_matches(list: &List<i32>, set: &BTreeSet<i32>) {
+        assert_eq!(contents(list), set.iter().copied().collect::<Vec<_>>());
+        assert_eq!(list.len(), set.len());
+    }
+
+    #[test]
+    fn set_algebra_matches_btreeset() {
+        let mut rng = StdRng::seed_from_u64(120);
+
+        for round in 0..200 {
+            // Vary the overlap between the two sets from round to round
+            let value_range = 1 + round % 40;
+            let (a, a_set) = random_set(&mut rng, 20, value_range);
+            let (b, b_set) = random_set(&mut rng, 20, value_range);
+
+            let union: BTreeSet<_> = a_set.union(&b_set).copied().collect();
+            let intersection: BTreeSet<_> = a_set.intersection(&b_set).copied().collect();
+            let difference: BTreeSet<_> = a_set.difference(&b_set).copied().collect();
+            let symmetric: BTreeSet<_> = a_set.symmetric_difference(&b_set).copied().collect();
+
+            check_matches(&a.union(&b), &union);
+            check_matches(&a.intersection(&b), &intersection);
+            check_matches(&a.difference(&b), &difference);
+            check_matches(&a.symmetric_difference(&b), &symmetric);
+
+            assert_eq!(a.is_subset(&b), a_set.is_subset(&b_set));
+            assert_eq!(b.is_subset(&a), b_set.is_subset(&a_set));
+            assert_eq!(a.is_disjoint(&b), a_set.is_disjoint(&b_set));
+
+            // The borrowing variants must leave their inputs alone
+            check_matches(&a, &a_set);
+            check_matches(&b, &b_set);
+
+            // Rebuild the inputs for each consuming variant
+            let clone = |list: &List<i32>| {
+                let mut copy = List::new();
+                for elem in list {
+                    copy.insert(*elem);
+                }
+                copy
+            };
+            check_matches(&clone(&a).into_union(clone(&b)), &union);
+            check_matches(&clone(&a).into_intersection(clone(&b)), &intersection);
+            check_matches(&clone(&a).into_difference(clone(&b)), &difference);
+            check_matches(&clone(&a).into_symmetric_difference(clone(&b)), &symmetric);
+        }
+    }
+
+    #[test]
+    fn set_algebra_edge_cases() {
+        let empty: List<i32> = List::new();
+        let (mut a, mut b) = (List::new(), List::new());
+        for elem in [1, 2, 3] {
+            a.insert(elem);
+        }
+        for elem in [2, 3] {
+            b.insert(elem);
+        }
+
+        // Empty lists
+        assert_eq!(contents(&a.union(&empty)), [1, 2, 3]);
+        assert_eq!(contents(&empty.union(&a)), [1, 2, 3]);
+        assert_eq!(a.intersection(&empty).is_empty(), true);
+        assert_eq!(contents(&a.difference(&empty)), [1, 2, 3]);
+        assert_eq!(empty.difference(&a).is_empty(), true);
+        assert_eq!(empty.is_subset(&a), true);
+        assert_eq!(empty.is_subset(&empty), true);
+        assert_eq!(a.is_subset(&empty), false);
+        assert_eq!(empty.is_disjoint(&a), true);
+
+        // Subsets and identical lists
+        assert_eq!(b.is_subset(&a), true);
+        assert_eq!(a.is_subset(&b), false);
+        assert_eq!(a.is_subset(&a), true);
+        assert_eq!(a.symmetric_difference(&a).is_empty(), true);
+        assert_eq!(contents(&a.intersection(&a)), [1, 2, 3]);
+
+        // Mixing ownership: the consuming variants work on lists of non-Clone elements too
+        let (mut c, mut d) = (List::new(), List::new());
+        c.insert(Box::new(1));
+        c.insert(Box::new(3));
+        d.insert(Box::new(2));
+        d.insert(Box::new(3));
+        let merged: Vec<i32> = c.into_union(d).into_iter().map(|elem| *elem).collect();
+        assert_eq!(merged, [1, 2, 3]);
+    }
+
+    #[test]
+    fn consuming_set_algebra_reuses_nodes() {
+        let (mut a, mut b) = (List::new(), List::new());
+        for elem in [1, 3, 5, 7] {
+            a.insert(elem);
+        }
+        for elem in [2, 3, 6, 7, 8] {
+            b.insert(elem);
+        }
+
+        // Remember where each node lives; the union should be made of exactly the nodes of a plus
+        // the nodes of b that hold values not in a
+        let address = |elem: &i32| elem as *const i32 as usize;
+        let a_nodes: Vec<usize> = a.iter().map(address).collect();
+        let b_nodes: Vec<usize> = b.iter().map(address).collect();
+
+        let union = a.into_union(b);
+        assert_eq!(contents(&union), [1, 2, 3, 5, 6, 7, 8]);
+        assert_eq!(union.len(), 7);
+
+        let expected = [a_nodes[0], b_nodes[0], a_nodes[1], a_nodes[2], b_nodes[2], a_nodes[3], b_nodes[4]];
+        assert_eq!(union.iter().map(address).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn try_insert_and_delete() {
+        let mut list = List::new();
+
+        assert_eq!(list.try_insert(2), Ok(()));
+        assert_eq!(list.try_insert(1), Ok(()));
+        assert_eq!(list.try_insert(3), Ok(()));
+        assert_eq!(list.try_insert(2), Err(Error::Duplicate));
+        assert_eq!(list.try_insert(1), Err(Error::Duplicate));
+        assert_eq!(list.try_insert(3), Err(Error::Duplicate));
+
+        assert_eq!(list.try_delete(&2), Ok(()));
+        assert_eq!(list.try_delete(&2), Err(Error::NotFound));
+        assert_eq!(list.try_delete(&0), Err(Error::NotFound));
+        assert_eq!(list.try_delete(&4), Err(Error::NotFound));
+        assert_eq!(contents(&list), [1, 3]);
+
+        let mut empty: List<i32> = List::new();
+        assert_eq!(empty.try_delete(&1), Err(Error::NotFound));
+    }
+
+    #[test]
+    fn max_len() {
+        let mut list = List::with_max_len(3);
+        assert_eq!(list.max_len(), Some(3));
+
+        assert_eq!(list.try_insert(20), Ok(()));
+        assert_eq!(list.try_insert(10), Ok(()));
+        assert_eq!(list.try_insert(30), Ok(()));
+
+        // Full list rejects new elements, but still reports duplicates as duplicates
+        assert_eq!(list.try_insert(40), Err(Error::CapacityExceeded { max_len: 3 }));
+        assert_eq!(list.try_insert(5), Err(Error::CapacityExceeded { max_len: 3 }));
+        assert_eq!(list.try_insert(20), Err(Error::Duplicate));
+        assert_eq!(list.insert(40), false);
+        assert_eq!(contents(&list), [10, 20, 30]);
+
+        // Deleting makes room again
+        assert_eq!(list.delete(&20), true);
+        assert_eq!(list.insert(40), true);
+        assert_eq!(list.len(), 3);
+
+        // Lowering the limit keeps existing elements but blocks inserts
+        list.set_max_len(Some(1));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.try_insert(50), Err(Error::CapacityExceeded { max_len: 1 }));
+
+        // Removing the limit allows the list to grow again
+        list.set_max_len(None);
+        assert_eq!(list.try_insert(50), Ok(()));
+        assert_eq!(contents(&list), [10, 30, 40, 50]);
+
+        // A zero-length limit rejects everything
+        let mut list = List::with_max_len(0);
+        assert_eq!(list.try_insert(1), Err(Error::CapacityExceeded { max_len: 0 }));
+        assert_eq!(list.is_empty(), true);
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(Error::Duplicate.to_string(), "element is already in the list");
+        assert_eq!(Error::NotFound.to_string(), "element is not in the list");
+        assert_eq!(
+            Error::CapacityExceeded { max_len: 8 }.to_string(),
+            "list is already at its maximum length of 8"
+        );
+        assert_eq!(Error::AllocFailed.to_string(), "failed to allocate a new list node");
+    }
+
+    #[test]
+    fn formatting() {
+        let mut list = List::new();
+        assert_eq!(list.to_string(), "end");
+        assert_eq!(format!("{:?}", list), "[]");
+
+        for elem in [20, 10, 30] {
+            list.insert(elem);
+        }
+        assert_eq!(list.to_string(), "10 -> 20 -> 30 -> end");
+        assert_eq!(format!("{:?}", list), "[10, 20, 30]");
+
+        let dot = list.to_dot();
+        assert!(dot.starts_with("digraph \"cs120::List\" {"));
+        assert!(dot.contains("n0 [label=\"{ 10 | <next> }\"];"));
+        assert!(dot.contains("head -> n0;"));
+        assert!(dot.contains("n2:next:c -> end;"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut list = List::with_max_len(10);
+        for elem in [20, 10, 30] {
+            list.insert(elem);
+        }
+
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, "[10,20,30]");
+
+        let list: List<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(contents(&list), [10, 20, 30]);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.max_len(), None);
+
+        let empty: List<String> = serde_json::from_str("[]").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_malformed() {
+        let err = serde_json::from_str::<List<i32>>("[1, 3, 2]").unwrap_err();
+        assert!(err.to_string().starts_with("element 2 is out of order: a cs120::List must be sorted"), "{}", err);
+
+        let err = serde_json::from_str::<List<i32>>("[1, 2, 2]").unwrap_err();
+        assert!(err.to_string().starts_with("element 2 is a duplicate: a cs120::List must be sorted"), "{}", err);
+
+        assert!(serde_json::from_str::<List<i32>>("[1, \"two\"]").is_err());
+        assert!(serde_json::from_str::<List<i32>>("{\"head\": null}").is_err());
+    }
+
+    #[test]
+    fn check_invariants() {
+        let mut list = List::new();
+        assert_eq!(list.check_invariants(), Ok(()));
+        for elem in [20, 10, 30] {
+            list.insert(elem);
+        }
+        assert_eq!(list.check_invariants(), Ok(()));
+
+        list.len = 4;
+        assert_eq!(list.check_invariants(), Err(InvariantError::LenMismatch { len: 4, nodes: 3 }));
+        list.len = 3;
+
+        // Break the ordering behind the list's back
+        list.head.as_mut().unwrap().next.as_mut().unwrap().elem = 10;
+        assert_eq!(list.check_invariants(), Err(InvariantError::NotIncreasing { index: 1 }));
+        assert_eq!(
+            InvariantError::NotIncreasing { index: 1 }.to_string(),
+            "element 1 is not greater than the element before it"
+        );
+    }
+
+    #[cfg(feature = "check-invariants")]
+    #[test]
+    #[should_panic(expected = "cs120::List invariant broken: len is 3 but the list has 2 nodes")]
+    fn broken_invariant_panics() {
+        let mut list = List::new();
+        list.insert(10);
+        list.len = 2;
+        list.insert(20);
+    }
+
+    #[test]
+    fn conformance() {
+        crate::sorted_set::conformance::run(List::new);
+    }
+}
+
+// Property tests: random sequences of operations, checked against BTreeSet after every step
+#[cfg(test)]
+mod properties {
+    use super::List;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Insert(i32),
+        Delete(i32),
+        SetMaxLen(Option<usize>),
+        Negate, // for_each_mut that reverses the order, forcing a re-sort
+        Union(Vec<i32>),
+        Intersection(Vec<i32>),
+        Difference(Vec<i32>),
+    }
+
+    // Values from a small range so that duplicates and deletes of present elements are common
+    fn op() -> impl Strategy<Value = Op> {
+        let elem = -20..20;
+        let elems = proptest::collection::vec(-20..20, 0..10);
+        prop_oneof![
+            4 => elem.clone().prop_map(Op::Insert),
+            3 => elem.prop_map(Op::Delete),
+            1 => proptest::option::of(0usize..30).prop_map(Op::SetMaxLen),
+            1 => Just(Op::Negate),
+            1 => elems.clone().prop_map(Op::Union),
+            1 => elems.clone().prop_map(Op::Intersection),
+            1 => elems.prop_map(Op::Difference),
+        ]
+    }
+
+    fn list_of(elems: &[i32]) -> List<i32> {
+        elems.iter().copied().collect::<BTreeSet<_>>().into_iter().fold(List::new(), |mut list, elem| {
+            list.insert(elem);
+            list
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn random_ops_match_btreeset(ops in proptest::collection::vec(op(), 0..100)) {
+            let mut list = List::new();
+            let mut model = BTreeSet::new();
+            let mut max_len = None;
+
+            for op in ops {
+                match op {
+                    Op::Insert(elem) => {
+                        let room = max_len.is_none_or(|max_len| model.len() < max_len);
+                        let expected = room && !model.contains(&elem);
+                        if expected {
+                            model.insert(elem);
+                        }
+                        prop_assert_eq!(list.insert(elem), expected);
+                    }
+                    Op::Delete(elem) => prop_assert_eq!(list.delete(&elem), model.remove(&elem)),
+                    Op::SetMaxLen(limit) => {
+                        max_len = limit;
+                        list.set_max_len(limit);
+                    }
+                    Op::Negate => {
+                        list.for_each_mut(|elem| *elem = -*elem);
+                        model = model.iter().map(|elem| -elem).collect();
+                    }
+                    Op::Union(elems) => {
+                        let other: BTreeSet<_> = elems.iter().copied().collect();
+                        model = model.union(&other).copied().collect();
+                        list = list.into_union(list_of(&elems));
+                        list.set_max_len(max_len);
+                    }
+                    Op::Intersection(elems) => {
+                        let other: BTreeSet<_> = elems.iter().copied().collect();
+                        model = model.intersection(&other).copied().collect();
+                        list = list.intersection(&list_of(&elems));
+                        list.set_max_len(max_len);
+                    }
+                    Op::Difference(elems) => {
+                        let other: BTreeSet<_> = elems.iter().copied().collect();
+                        model = model.difference(&other).copied().collect();
+                        list = list.into_difference(list_of(&elems));
+                        list.set_max_len(max_len);
+                    }
+                }
+
+                prop_assert_eq!(list.check_invariants(), Ok(()));
+                prop_assert_eq!(list.len(), model.len());
+                prop_assert!(list.iter().eq(model.iter()));
+            }
+        }
+
+        #[test]
+        fn contains_and_range_match_btreeset(
+            elems in proptest::collection::vec(-50..50, 0..60),
+            probe in -60..60,
+            low in -60..60,
+            width in 0..40,
+        ) {
+            let list = list_of(&elems);
+            let model: BTreeSet<i32> = elems.into_iter().collect();
+            prop_assert_eq!(list.check_invariants(), Ok(()));
+            prop_assert_eq!(list.contains(&probe), model.contains(&probe));
+            prop_assert!(list.range(low..low + width).eq(model.range(low..low + width)));
+            prop_assert_eq!(list.first(), model.first());
+            prop_assert_eq!(list.last(), model.last());
+        }
+    }
 }
@@ -0,0 +1,477 @@
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+use crate::footprint::Footprint;
+
+// Doubly linked deque: O(1) push, pop and peek at both ends. Nodes point both ways, which Box
+// ownership can't express, so links are raw pointers and the deque owns every node itself.
+//
+// The rules the unsafe code relies on:
+// - Every node is created by Box::into_raw in push_* and freed exactly once, by Box::from_raw in
+//   pop_*, when it is unlinked.
+// - front/back are None exactly when len is 0, and otherwise point at live nodes, whose own
+//   front/back links point at their live neighbours (or None at the ends).
+// - Nodes are only ever accessed through these raw pointers, never through a long-lived &mut, so
+//   references handed out (peek, iter, iter_mut) are tied to a borrow of the deque itself.
+// The tests are kept small enough to run under Miri (`cargo +nightly miri test deque`), which
+// checks these rules for us.
+pub struct Deque<T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    // We own Ts (through the nodes), which matters to drop check and variance
+    _owns: PhantomData<T>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    front: Link<T>,
+    back: Link<T>,
+    elem: T,
+}
+
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize, // elements not yet yielded from either end
+    _borrow: PhantomData<&'a T>,
+}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _borrow: PhantomData<&'a mut T>,
+}
+
+pub struct IntoIter<T>(Deque<T>);
+
+// A Deque is just a set of uniquely owned Ts, so it can go wherever they can
+unsafe impl<T: Send> Send for Deque<T> {}
+unsafe impl<T: Sync> Sync for Deque<T> {}
+unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+
+impl<T> Deque<T> {
+    pub fn new() -> Self {
+        Deque { front: None, back: None, len: 0, _owns: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Heap memory held by the deque: one node allocation per element
+    pub fn memory_footprint(&self) -> Footprint {
+        Footprint::boxed::<Node<T>>(self.len)
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        unsafe {
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node { front: None, back: None, elem })));
+            match self.front {
+                Some(old) => {
+                    (*old.as_ptr()).front = Some(new);
+                    (*new.as_ptr()).back = Some(old);
+                }
+                None => self.back = Some(new),
+            }
+            self.front = Some(new);
+            self.len += 1;
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        unsafe {
+            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node { front: None, back: None, elem })));
+            match self.back {
+                Some(old) => {
+                    (*old.as_ptr()).back = Some(new);
+                    (*new.as_ptr()).front = Some(old);
+                }
+                None => self.front = Some(new),
+            }
+            self.back = Some(new);
+            self.len += 1;
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.front.map(|node| unsafe {
+            // Take the node back into a Box, which frees it once we have moved elem out
+            let boxed = Box::from_raw(node.as_ptr());
+            self.front = boxed.back;
+            match self.front {
+                Some(new) => (*new.as_ptr()).front = None,
+                None => self.back = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.back.map(|node| unsafe {
+            let boxed = Box::from_raw(node.as_ptr());
+            self.back = boxed.front;
+            match self.back {
+                Some(new) => (*new.as_ptr()).back = None,
+                None => self.front = None,
+            }
+            self.len -= 1;
+            boxed.elem
+        })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        unsafe { self.front.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.front.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        unsafe { self.back.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.back.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { front: self.front, back: self.back, len: self.len, _borrow: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { front: self.front, back: self.back, len: self.len, _borrow: PhantomData }
+    }
+}
+
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Deque<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Debug> Debug for Deque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Elements go on the back, so the deque iterates in the same order as the source
+impl<T> FromIterator<T> for Deque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Deque::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T> Extend<T> for Deque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// The iterators count down len rather than comparing front and back, so the two ends stop as
+// soon as they have met, without ever handing out the same element twice
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).back;
+            &(*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).front;
+            &(*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).back;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).front;
+            &mut (*node.as_ptr()).elem
+        })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::Deque;
+    use std::collections::VecDeque;
+
+    // Miri is slow, so it gets a shorter run of the randomized test
+    const RANDOM_OPS: usize = if cfg!(miri) { 200 } else { 10_000 };
+
+    fn contents<T: Clone>(deque: &Deque<T>) -> Vec<T> {
+        deque.iter().cloned().collect()
+    }
+
+    #[test]
+    fn basics() {
+        let mut deque = Deque::new();
+
+        // Check that popping or peeking an empty deque gives None from both ends
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(deque.peek_front(), None);
+        assert_eq!(deque.peek_back(), None);
+        assert!(deque.is_empty());
+
+        deque.push_front(2);
+        deque.push_front(1);
+        deque.push_back(3);
+        deque.push_back(4);
+        assert_eq!(contents(&deque), [1, 2, 3, 4]);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque.peek_front(), Some(&1));
+        assert_eq!(deque.peek_back(), Some(&4));
+
+        // Check normal removal from both ends
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_back(), Some(4));
+        assert_eq!(deque.pop_back(), Some(3));
+
+        // Down to one element, which is both the front and the back
+        assert_eq!(deque.peek_front(), Some(&2));
+        assert_eq!(deque.peek_back(), Some(&2));
+        assert_eq!(deque.pop_front(), Some(2));
+
+        // Check exhaustion, and that the deque is still usable afterwards
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        deque.push_back(5);
+        assert_eq!(deque.pop_front(), Some(5));
+        assert_eq!(deque.len(), 0);
+    }
+
+    #[test]
+    fn peek_mut() {
+        let mut deque: Deque<i32> = (1..=3).collect();
+        *deque.peek_front_mut().unwrap() = 10;
+        *deque.peek_back_mut().unwrap() *= 10;
+        assert_eq!(contents(&deque), [10, 2, 30]);
+        assert_eq!(Deque::<i32>::new().peek_back_mut(), None);
+    }
+
+    #[test]
+    fn iter_both_ends() {
+        let deque: Deque<i32> = (1..=5).collect();
+        assert_eq!(deque.iter().rev().copied().collect::<Vec<_>>(), [5, 4, 3, 2, 1]);
+
+        // Meeting in the middle yields every element exactly once
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next_back(), Some(&3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut deque: Deque<i32> = (1..=4).collect();
+        for elem in deque.iter_mut() {
+            *elem *= 10;
+        }
+        for elem in (&mut deque).into_iter().rev().take(2) {
+            *elem += 1;
+        }
+        assert_eq!(contents(&deque), [10, 20, 31, 41]);
+
+        // Holding mutable references from both ends at once is fine; they never alias
+        let mut iter = deque.iter_mut();
+        let first = iter.next().unwrap();
+        let last = iter.next_back().unwrap();
+        std::mem::swap(first, last);
+        assert_eq!(iter.count(), 2);
+        assert_eq!(contents(&deque), [41, 20, 31, 10]);
+    }
+
+    #[test]
+    fn into_iter() {
+        let deque: Deque<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let mut iter = deque.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back().as_deref(), Some("c"));
+        assert_eq!(iter.next().as_deref(), Some("a"));
+        // Dropping the iterator frees the element it didn't get to
+    }
+
+    #[test]
+    fn formatting_and_extend() {
+        let mut deque: Deque<i32> = Deque::default();
+        assert_eq!(format!("{:?}", deque), "[]");
+        deque.extend([1, 2]);
+        deque.push_front(0);
+        assert_eq!(format!("{:?}", deque), "[0, 1, 2]");
+        deque.clear();
+        assert!(deque.is_empty());
+    }
+
+    // Elements that own heap memory, so leaks and double frees show up under Miri
+    #[test]
+    fn owned_elements() {
+        let mut deque = Deque::new();
+        for i in 0..10 {
+            if i % 2 == 0 {
+                deque.push_back(Box::new(i));
+            } else {
+                deque.push_front(Box::new(i));
+            }
+        }
+        assert_eq!(deque.pop_back().as_deref(), Some(&8));
+        assert_eq!(deque.pop_front().as_deref(), Some(&9));
+        // The rest are freed by Drop
+    }
+
+    #[test]
+    fn matches_vecdeque() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut deque = Deque::new();
+        let mut model = VecDeque::new();
+        for i in 0..RANDOM_OPS {
+            match rng.gen_range(0..6) {
+                0 => {
+                    deque.push_front(i);
+                    model.push_front(i);
+                }
+                1 => {
+                    deque.push_back(i);
+                    model.push_back(i);
+                }
+                2 => assert_eq!(deque.pop_front(), model.pop_front()),
+                3 => assert_eq!(deque.pop_back(), model.pop_back()),
+                4 => assert_eq!(deque.peek_front(), model.front()),
+                _ => assert_eq!(deque.peek_back(), model.back()),
+            }
+            assert_eq!(deque.len(), model.len());
+        }
+        assert!(deque.iter().eq(model.iter()));
+        assert!(deque.iter().rev().eq(model.iter().rev()));
+    }
+}
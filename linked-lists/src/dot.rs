@@ -0,0 +1,89 @@
+use std::fmt::{Display, Write};
+
+// Render a singly linked list as a Graphviz DOT digraph: a head pointer, one record per node
+// holding its element and next pointer, and an edge for every next pointer, ending at a point for
+// None. elems must be the list's elements in node order. The result can be piped straight into
+// `dot -Tsvg`.
+pub fn render<I>(name: &str, elems: I) -> String
+where
+    I: IntoIterator,
+    I::Item: Display,
+{
+    let mut out = String::new();
+    writeln!(out, "digraph \"{}\" {{", escape(name)).unwrap();
+    writeln!(out, "    rankdir=LR;").unwrap();
+    writeln!(out, "    node [shape=record];").unwrap();
+    writeln!(out, "    head [shape=plaintext];").unwrap();
+    writeln!(out, "    end [shape=point];").unwrap();
+
+    let mut count = 0;
+    for elem in elems {
+        let label = escape(&elem.to_string());
+        writeln!(out, "    n{} [label=\"{{ {} | <next> }}\"];", count, label).unwrap();
+        count += 1;
+    }
+
+    // Edges go after all the nodes so that every edge refers to a node that is already declared
+    let mut prev = String::from("head");
+    for i in 0..count {
+        writeln!(out, "    {} -> n{};", prev, i).unwrap();
+        prev = format!("n{}:next:c", i);
+    }
+    writeln!(out, "    {} -> end;", prev).unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+// Backslash the characters that mean something inside a quoted record label
+fn escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' | '\\' | '{' | '}' | '|' | '<' | '>' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::render;
+
+    #[test]
+    fn empty() {
+        let dot = render("list", Vec::<i32>::new());
+        assert!(dot.starts_with("digraph \"list\" {\n"));
+        assert!(dot.contains("    head -> end;\n"));
+        assert!(!dot.contains("n0"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn nodes_and_pointers() {
+        let dot = render("list", [1, 2, 3]);
+        for line in [
+            "    n0 [label=\"{ 1 | <next> }\"];",
+            "    n2 [label=\"{ 3 | <next> }\"];",
+            "    head -> n0;",
+            "    n0:next:c -> n1;",
+            "    n1:next:c -> n2;",
+            "    n2:next:c -> end;",
+        ] {
+            assert!(dot.lines().any(|l| l == line), "missing {:?} in\n{}", line, dot);
+        }
+    }
+
+    #[test]
+    fn escapes_labels() {
+        let dot = render("say \"hi\"", ["a|b", "{x}", "<p>"]);
+        assert!(dot.starts_with("digraph \"say \\\"hi\\\"\" {"));
+        assert!(dot.contains("{ a\\|b | <next> }"));
+        assert!(dot.contains("{ \\{x\\} | <next> }"));
+        assert!(dot.contains("{ \\<p\\> | <next> }"));
+    }
+}
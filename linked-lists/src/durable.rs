@@ -0,0 +1,536 @@
+use std::fmt::Display;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::cs120::List;
+
+// A cs120::List that survives restarts. Every successful insert or delete is appended to a
+// write-ahead log in dir before the list itself changes, and every snapshot_every log records the
+// whole list is written out as a compacted snapshot and the log is emptied. Opening the directory
+// again loads the snapshot and replays the log on top of it.
+//
+// Each record in either file is a kind byte, the element's length as a little-endian u32, a
+// checksum of those two, the element itself (its Display form, read back with FromStr), and a
+// checksum of everything before it. A crash part way through an append leaves a torn record at
+// the end of the log; open() drops it, since the operation it described never completed. Damage
+// anywhere else, including a damaged length in any record, is reported as an error.
+//
+// Records are written straight to the file, so they survive the process crashing. Surviving the
+// machine crashing as well needs sync() after the operations that matter.
+pub struct DurableList<T> {
+    list: List<T>,
+    dir: PathBuf,
+    log: File,
+    log_len: u64,       // bytes in the log, up to the end of the last complete record
+    log_records: usize, // records in the log since the last snapshot
+    snapshot_every: usize,
+}
+
+const SNAPSHOT: &str = "snapshot";
+const SNAPSHOT_TMP: &str = "snapshot.tmp";
+const LOG: &str = "log";
+
+const INSERT: u8 = b'+';
+const DELETE: u8 = b'-';
+const ELEM: u8 = b'='; // one element of a snapshot
+
+const DEFAULT_SNAPSHOT_EVERY: usize = 1000;
+
+impl<T: Ord + Display + FromStr> DurableList<T> {
+    // Open the list stored in dir, creating an empty one if there is nothing there yet
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut list = List::new();
+        if let Some(bytes) = read_if_exists(&dir.join(SNAPSHOT))? {
+            let (records, torn) = parse(&bytes)?;
+            if torn.is_some() {
+                return Err(invalid_data("snapshot ends part way through a record"));
+            }
+            let mut elems = Vec::with_capacity(records.len());
+            for (kind, elem) in records {
+                if kind != ELEM {
+                    return Err(invalid_data("snapshot contains a log record"));
+                }
+                elems.push(parse_elem::<T>(elem)?);
+            }
+            if !elems.windows(2).all(|w| w[0] < w[1]) {
+                return Err(invalid_data("snapshot is not a sorted list of unique elements"));
+            }
+            for elem in elems {
+                list.insert(elem);
+            }
+        }
+
+        // Replaying is safe even if we crashed after writing a snapshot but before emptying the
+        // log: each element ends up however the last record mentioning it left it, whatever the
+        // starting point.
+        let mut log_records = 0;
+        let mut log_len = 0;
+        if let Some(bytes) = read_if_exists(&dir.join(LOG))? {
+            let (records, torn) = parse(&bytes)?;
+            for (kind, elem) in records {
+                let elem = parse_elem(elem)?;
+                match kind {
+                    INSERT => list.insert(elem),
+                    DELETE => list.delete(&elem),
+                    _ => return Err(invalid_data("log contains a snapshot record")),
+                };
+                log_records += 1;
+            }
+            log_len = torn.unwrap_or(bytes.len()) as u64;
+        }
+
+        // Cut off any torn record so that new records follow the last complete one
+        let log = OpenOptions::new().create(true).append(true).open(dir.join(LOG))?;
+        log.set_len(log_len)?;
+        Ok(DurableList { list, dir, log, log_len, log_records, snapshot_every: DEFAULT_SNAPSHOT_EVERY })
+    }
+
+    // Number of log records after which a snapshot is taken automatically
+    pub fn snapshot_every(&self) -> usize {
+        self.snapshot_every
+    }
+
+    pub fn set_snapshot_every(&mut self, records: usize) {
+        self.snapshot_every = records.max(1);
+    }
+
+    // The list as it currently stands, for reading
+    pub fn list(&self) -> &List<T> {
+        &self.list
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.list.contains(elem)
+    }
+
+    // Insert elem, returning false if it was already present (in which case nothing is logged).
+    // If the log can't be written the list is left unchanged. Once the record is in the log the
+    // insert has happened, so a failed automatic snapshot isn't reported; it is tried again after
+    // the next operation.
+    pub fn insert(&mut self, elem: T) -> io::Result<bool> {
+        if self.list.contains(&elem) {
+            return Ok(false);
+        }
+        self.append(INSERT, &elem)?;
+        self.list.insert(elem);
+        self.after_append();
+        Ok(true)
+    }
+
+    // Remove elem, returning false if it was not present (in which case nothing is logged). If
+    // the log can't be written the list is left unchanged; a failed automatic snapshot is handled
+    // as for insert.
+    pub fn delete(&mut self, elem: &T) -> io::Result<bool> {
+        if !self.list.contains(elem) {
+            return Ok(false);
+        }
+        self.append(DELETE, elem)?;
+        self.list.delete(elem);
+        self.after_append();
+        Ok(true)
+    }
+
+    // Flush the log to disk, so that everything done so far survives a power failure too
+    pub fn sync(&self) -> io::Result<()> {
+        self.log.sync_data()
+    }
+
+    // Write the whole list to a new snapshot and empty the log. The snapshot is written to a
+    // temporary file and renamed into place, so a crash leaves either the old snapshot or the new
+    // one, never half of one.
+    pub fn snapshot(&mut self) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        for elem in self.list.iter() {
+            encode(&mut bytes, ELEM, elem)?;
+        }
+
+        let tmp = self.dir.join(SNAPSHOT_TMP);
+        let mut file = File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(SNAPSHOT))?;
+        // Make the rename itself durable before the log it replaces goes away
+        if let Ok(dir) = File::open(&self.dir) {
+            let _ = dir.sync_all();
+        }
+
+        self.log.set_len(0)?;
+        self.log.sync_all()?;
+        self.log_len = 0;
+        self.log_records = 0;
+        Ok(())
+    }
+
+    // If the write fails part way, what did get written is cut off again, so that it can't end
+    // up in the middle of the log once later records follow it
+    fn append(&mut self, kind: u8, elem: &T) -> io::Result<()> {
+        let mut record = Vec::new();
+        encode(&mut record, kind, elem)?;
+        if let Err(err) = self.log.write_all(&record) {
+            let _ = self.log.set_len(self.log_len);
+            return Err(err);
+        }
+        self.log_len += record.len() as u64;
+        Ok(())
+    }
+
+    // The record is already in the log, so if the snapshot fails the log just keeps growing and
+    // log_records stays past snapshot_every, which makes the next append try again
+    fn after_append(&mut self) {
+        self.log_records += 1;
+        if self.log_records >= self.snapshot_every {
+            let _ = self.snapshot();
+        }
+    }
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn parse_elem<T: FromStr>(bytes: &[u8]) -> io::Result<T> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid_data("record holds an element that can't be parsed"))
+}
+
+// Record header (kind + length + header checksum) and trailer (checksum) sizes
+const FIELDS_LEN: usize = 5;
+const CHECKSUM_LEN: usize = 4;
+const HEADER_LEN: usize = FIELDS_LEN + CHECKSUM_LEN;
+
+// Fails without writing anything if the element's Display form is too long for the length field
+fn encode<T: Display>(out: &mut Vec<u8>, kind: u8, elem: &T) -> io::Result<()> {
+    let payload = elem.to_string();
+    let len = payload_len(payload.len())?;
+    let start = out.len();
+    out.push(kind);
+    out.extend_from_slice(&len.to_le_bytes());
+    let header_checksum = fnv1a(&out[start..]);
+    out.extend_from_slice(&header_checksum.to_le_bytes());
+    out.extend_from_slice(payload.as_bytes());
+    let checksum = fnv1a(&out[start..]);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    Ok(())
+}
+
+fn payload_len(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "element is too long to store in a record")
+    })
+}
+
+// Kind byte and payload of one record
+type Record<'a> = (u8, &'a [u8]);
+
+// Split bytes into records. If the bytes end part way through the last record it is treated as
+// torn, and the length of the valid prefix is returned alongside the records before it. The
+// length is only trusted once the header checksum confirms it, so a damaged length can't pass off
+// the rest of the file as one torn record; that, and any other bad record, is an error.
+fn parse(bytes: &[u8]) -> io::Result<(Vec<Record<'_>>, Option<usize>)> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.len() < HEADER_LEN {
+            return Ok((records, Some(pos)));
+        }
+        let (fields, header_checksum) = rest[..HEADER_LEN].split_at(FIELDS_LEN);
+        if fnv1a(fields) != u32::from_le_bytes(header_checksum.try_into().unwrap()) {
+            return Err(invalid_data("record header failed its checksum"));
+        }
+
+        let payload_len = u32::from_le_bytes(fields[1..].try_into().unwrap()) as usize;
+        let record_len = HEADER_LEN.saturating_add(payload_len).saturating_add(CHECKSUM_LEN);
+        if rest.len() < record_len {
+            return Ok((records, Some(pos)));
+        }
+
+        let (body, checksum) = rest[..record_len].split_at(record_len - CHECKSUM_LEN);
+        if fnv1a(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(invalid_data("record failed its checksum"));
+        }
+
+        records.push((body[0], &body[HEADER_LEN..]));
+        pos += record_len;
+    }
+    Ok((records, None))
+}
+
+// 32-bit FNV-1a: not cryptographic, but plenty to notice a record that was only partly written
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for &byte in bytes {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode, payload_len, DurableList, ELEM, HEADER_LEN, LOG, SNAPSHOT, SNAPSHOT_TMP};
+    use std::fs::{self, OpenOptions};
+    use std::io::ErrorKind;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn contents(list: &DurableList<i32>) -> Vec<i32> {
+        list.list().iter().copied().collect()
+    }
+
+    fn log_len(dir: &Path) -> u64 {
+        fs::metadata(dir.join(LOG)).unwrap().len()
+    }
+
+    fn truncate_log(dir: &Path, by: u64) {
+        let file = OpenOptions::new().write(true).open(dir.join(LOG)).unwrap();
+        let len = file.metadata().unwrap().len();
+        file.set_len(len - by).unwrap();
+    }
+
+    #[test]
+    fn reopen_restores_contents() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            assert!(list.is_empty());
+            for elem in [30, 10, 20, 40] {
+                assert!(list.insert(elem).unwrap());
+            }
+            assert!(list.delete(&20).unwrap());
+        }
+
+        let list = DurableList::<i32>::open(dir.path()).unwrap();
+        assert_eq!(contents(&list), [10, 30, 40]);
+        assert_eq!(list.len(), 3);
+        assert!(list.contains(&40));
+    }
+
+    #[test]
+    fn only_successful_operations_are_logged() {
+        let dir = tempdir().unwrap();
+        let mut list = DurableList::open(dir.path()).unwrap();
+        list.insert(1).unwrap();
+        let len = log_len(dir.path());
+
+        assert!(!list.insert(1).unwrap());
+        assert!(!list.delete(&2).unwrap());
+        assert_eq!(log_len(dir.path()), len);
+    }
+
+    #[test]
+    fn strings() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            for word in ["pear", "apple", "two words", "", "line\nbreak"] {
+                list.insert(word.to_string()).unwrap();
+            }
+            list.snapshot().unwrap();
+            list.delete(&"pear".to_string()).unwrap();
+        }
+
+        let list = DurableList::<String>::open(dir.path()).unwrap();
+        let words: Vec<_> = list.list().iter().map(String::as_str).collect();
+        assert_eq!(words, ["", "apple", "line\nbreak", "two words"]);
+    }
+
+    #[test]
+    fn snapshots_compact_the_log() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            list.set_snapshot_every(10);
+            for elem in 0..25 {
+                list.insert(elem).unwrap();
+            }
+            // 20 records went into snapshots, and the log only holds the last 5
+            assert!(dir.path().join(SNAPSHOT).exists());
+            assert!(log_len(dir.path()) > 0);
+            for elem in 0..5 {
+                list.delete(&elem).unwrap();
+            }
+            assert_eq!(log_len(dir.path()), 0);
+        }
+
+        let list = DurableList::<i32>::open(dir.path()).unwrap();
+        assert_eq!(contents(&list), (5..25).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn torn_final_record() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            for elem in [1, 2, 3] {
+                list.insert(elem).unwrap();
+            }
+        }
+
+        // Lose the end of the last record, as if we crashed while writing it
+        truncate_log(dir.path(), 2);
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            assert_eq!(contents(&list), [1, 2]);
+
+            // New records go after the last complete one, not after the torn bytes
+            list.insert(4).unwrap();
+        }
+
+        let list = DurableList::<i32>::open(dir.path()).unwrap();
+        assert_eq!(contents(&list), [1, 2, 4]);
+    }
+
+    #[test]
+    fn torn_header() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            list.insert(7).unwrap();
+            list.insert(8).unwrap();
+        }
+
+        // Only the first byte of the second record made it to disk
+        let record_len = log_len(dir.path()) / 2;
+        truncate_log(dir.path(), record_len - 1);
+        let list = DurableList::<i32>::open(dir.path()).unwrap();
+        assert_eq!(contents(&list), [7]);
+        assert_eq!(log_len(dir.path()), record_len);
+    }
+
+    #[test]
+    fn corruption_before_the_end_is_an_error() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            list.insert(1).unwrap();
+            list.insert(2).unwrap();
+        }
+
+        let mut bytes = fs::read(dir.path().join(LOG)).unwrap();
+        bytes[HEADER_LEN] ^= 0xff; // the payload of the first record
+        fs::write(dir.path().join(LOG), bytes).unwrap();
+        assert!(DurableList::<i32>::open(dir.path()).is_err());
+    }
+
+    #[test]
+    fn damaged_length_is_an_error() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            for elem in [1, 2, 3] {
+                list.insert(elem).unwrap();
+            }
+        }
+        let log = fs::read(dir.path().join(LOG)).unwrap();
+
+        // A larger length would make the first record run past the end of the file, which mustn't
+        // be mistaken for a torn record and cost us the two after it
+        let mut bytes = log.clone();
+        bytes[1] ^= 0x40;
+        fs::write(dir.path().join(LOG), &bytes).unwrap();
+        let err = DurableList::<i32>::open(dir.path()).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(fs::read(dir.path().join(LOG)).unwrap(), bytes); // and the log is left alone
+
+        // The same goes for the last record, which does reach the end of the file
+        let mut bytes = log.clone();
+        let last = log.len() / 3 * 2;
+        bytes[last + 1] ^= 0x40;
+        fs::write(dir.path().join(LOG), bytes).unwrap();
+        assert!(DurableList::<i32>::open(dir.path()).is_err());
+    }
+
+    #[test]
+    fn crash_between_snapshot_and_log_truncation() {
+        let dir = tempdir().unwrap();
+        let log_before_snapshot;
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            for elem in [1, 2, 3] {
+                list.insert(elem).unwrap();
+            }
+            list.delete(&2).unwrap();
+            list.insert(2).unwrap();
+            list.delete(&3).unwrap();
+            log_before_snapshot = fs::read(dir.path().join(LOG)).unwrap();
+            list.snapshot().unwrap();
+        }
+
+        // The new snapshot is in place but the old log is still there
+        fs::write(dir.path().join(LOG), log_before_snapshot).unwrap();
+        let list = DurableList::<i32>::open(dir.path()).unwrap();
+        assert_eq!(contents(&list), [1, 2]);
+    }
+
+    #[test]
+    fn failed_snapshot_does_not_fail_the_operation() {
+        let dir = tempdir().unwrap();
+        {
+            let mut list = DurableList::open(dir.path()).unwrap();
+            list.set_snapshot_every(2);
+
+            // A directory in the way of the temporary file makes every snapshot fail
+            fs::create_dir(dir.path().join(SNAPSHOT_TMP)).unwrap();
+            assert!(list.insert(1).unwrap());
+            assert!(list.insert(2).unwrap());
+            assert!(list.delete(&1).unwrap());
+            assert!(!dir.path().join(SNAPSHOT).exists());
+            assert_eq!(contents(&list), [2]);
+
+            // Once it can succeed, the next operation takes the snapshot that was missed
+            fs::remove_dir(dir.path().join(SNAPSHOT_TMP)).unwrap();
+            assert!(list.insert(3).unwrap());
+            assert!(dir.path().join(SNAPSHOT).exists());
+            assert_eq!(log_len(dir.path()), 0);
+        }
+
+        let list = DurableList::<i32>::open(dir.path()).unwrap();
+        assert_eq!(contents(&list), [2, 3]);
+    }
+
+    #[test]
+    fn oversized_element_is_rejected() {
+        assert_eq!(payload_len(u32::MAX as usize).unwrap(), u32::MAX);
+        if let Ok(len) = usize::try_from(u64::from(u32::MAX) + 1) {
+            assert_eq!(payload_len(len).unwrap_err().kind(), ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn unsorted_snapshot_is_an_error() {
+        let dir = tempdir().unwrap();
+        for elems in [[1, 3, 2], [1, 2, 2]] {
+            let mut bytes = Vec::new();
+            for elem in elems {
+                encode(&mut bytes, ELEM, &elem).unwrap();
+            }
+            fs::write(dir.path().join(SNAPSHOT), bytes).unwrap();
+            let err = DurableList::<i32>::open(dir.path()).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -1,5 +1,13 @@
+// This list deliberately spells out mem::replace where second.rs uses take(), to show what take()
+// does under the hood
+#![allow(clippy::mem_replace_option_with_none)]
+
+use std::fmt;
 use std::mem;
 
+use crate::dot;
+use crate::footprint::Footprint;
+
 struct Node {
     elem: i32, // simple list that only stores integers
     next: Option<Box<Node>>,
@@ -16,7 +24,7 @@ impl List {
 
     pub fn push(&mut self, elem: i32) {
         let new_node = Box::new(Node {
-            elem: elem,
+            elem,
             // references must always be valid, so if we are moving ownership of what self.head
             // currently points to to "next", we need to replace it with something else (in this
             // case, none), using the mem::replace operation
@@ -40,6 +48,88 @@ impl List {
 
         ret_val
     }
+
+    // Heap memory held by the list: one node allocation per element, counted by walking the list
+    pub fn memory_footprint(&self) -> Footprint {
+        let mut count = 0;
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            count += 1;
+            cur_node = &node.next;
+        }
+        Footprint::boxed::<Node>(count)
+    }
+
+    // The list's nodes and next pointers as a Graphviz DOT graph (see dot::render)
+    pub fn to_dot(&self) -> String {
+        let mut elems = Vec::new();
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            elems.push(node.elem);
+            cur_node = &node.next;
+        }
+        dot::render("first::List", elems)
+    }
+}
+
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Elements from the top of the stack down, as `a -> b -> end`
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            write!(f, "{} -> ", node.elem)?;
+            cur_node = &node.next;
+        }
+        write!(f, "end")
+    }
+}
+
+// Serialized as a sequence from the top of the stack down, so a deserialized list pops in the same
+// order as the original
+#[cfg(feature = "serde")]
+impl serde::Serialize for List {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(None)?;
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            seq.serialize_element(&node.elem)?;
+            cur_node = &node.next;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for List {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // The bottom of the stack has to be pushed first
+        let elems = Vec::<i32>::deserialize(deserializer)?;
+        let mut list = List::new();
+        for elem in elems.into_iter().rev() {
+            list.push(elem);
+        }
+        Ok(list)
+    }
+}
+
+impl fmt::Debug for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut cur_node = &self.head;
+        while let Some(node) = cur_node {
+            list.entry(&node.elem);
+            cur_node = &node.next;
+        }
+        list.finish()
+    }
 }
 
 impl Drop for List {
@@ -98,4 +188,56 @@ mod test{
         }
         println!("list is now out of scope.");
     }
+
+    #[test]
+    fn formatting() {
+        let mut list = List::new();
+        assert_eq!(list.to_string(), "end");
+        assert_eq!(format!("{:?}", list), "[]");
+
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        assert_eq!(list.to_string(), "3 -> 2 -> 1 -> end");
+        assert_eq!(format!("{:?}", list), "[3, 2, 1]");
+
+        let dot = list.to_dot();
+        assert!(dot.contains("n0 [label=\"{ 3 | <next> }\"];"));
+        assert!(dot.contains("n2:next:c -> end;"));
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let mut list = List::new();
+        assert_eq!(list.memory_footprint().heap_bytes, 0);
+
+        list.push(1);
+        list.push(2);
+        list.push(3);
+        list.pop();
+        let footprint = list.memory_footprint();
+        assert_eq!(footprint.nodes, 2);
+        assert_eq!(footprint.bytes_per_node, std::mem::size_of::<super::Node>());
+        assert_eq!(footprint.heap_bytes, 2 * footprint.bytes_per_node);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut list = List::new();
+        list.push(1);
+        list.push(2);
+        list.push(3);
+
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, "[3,2,1]");
+
+        let mut list: List = serde_json::from_str(&json).unwrap();
+        assert_eq!(list.pop(), Some(3));
+        assert_eq!(list.pop(), Some(2));
+        assert_eq!(list.pop(), Some(1));
+        assert_eq!(list.pop(), None);
+
+        assert!(serde_json::from_str::<List>("[1, \"two\"]").is_err());
+    }
 }
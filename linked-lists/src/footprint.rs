@@ -0,0 +1,28 @@
+use std::alloc::Layout;
+use std::mem;
+
+// Heap memory held by a list's own structure, as reported by each list's memory_footprint().
+// This counts the nodes (and for arena lists and SkipList, the slot and free-list Vecs), not
+// memory the elements themselves point to, such as the contents of a String.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footprint {
+    pub nodes: usize,          // node allocations (or arena slots, used or free, or Vec elements)
+    pub bytes_per_node: usize, // size of one node, including its links and padding
+    pub heap_bytes: usize,     // everything the list has allocated
+}
+
+impl Footprint {
+    // Footprint of count separately allocated nodes of type N, which is all a Box-linked list holds
+    pub(crate) fn boxed<N>(count: usize) -> Self {
+        let bytes_per_node = mem::size_of::<N>();
+        Footprint { nodes: count, bytes_per_node, heap_bytes: count * bytes_per_node }
+    }
+
+    // Footprint of count nodes of type N behind Rc or Arc. Each allocation holds the strong and
+    // weak reference counts followed by the node itself, so that is what counts as one node.
+    pub(crate) fn counted<N>(count: usize) -> Self {
+        let (layout, _) = Layout::new::<[usize; 2]>().extend(Layout::new::<N>()).expect("node layout overflowed");
+        let bytes_per_node = layout.pad_to_align().size();
+        Footprint { nodes: count, bytes_per_node, heap_bytes: count * bytes_per_node }
+    }
+}
@@ -1,3 +1,15 @@
-pub mod first;
+pub mod arena;
+pub mod concurrent;
 pub mod cs120;
+pub mod deque;
+pub mod dot;
+pub mod durable;
+pub mod first;
+pub mod footprint;
+pub mod persistent;
 pub mod second;
+pub mod skiplist;
+pub mod sorted_set;
+pub mod sorted_vec;
+pub mod treiber;
+pub mod workload;
@@ -0,0 +1,269 @@
+// Persistent (immutable) stacks: prepend and tail never change a list, they return a new one that
+// shares every node it can with the old. That makes keeping old versions around, e.g. as undo
+// history, cost one node per change rather than a copy of the whole list.
+//
+// Nodes are reference counted. rc::List uses Rc and stays on one thread; sync::List (behind the
+// "sync" feature) uses Arc so that versions can be shared between threads. Both are generated
+// from the same code below, which refers to whichever pointer type its module imports as Ptr.
+
+macro_rules! persistent_list {
+    () => {
+        pub struct List<T> {
+            head: Link<T>,
+        }
+
+        type Link<T> = Option<Ptr<Node<T>>>;
+
+        struct Node<T> {
+            elem: T,
+            next: Link<T>,
+        }
+
+        pub struct Iter<'a, T> {
+            next: Option<&'a Node<T>>,
+        }
+
+        impl<T> List<T> {
+            pub fn new() -> Self {
+                List { head: None }
+            }
+
+            // New list with elem on top of this one. Both lists share all of this one's nodes.
+            pub fn prepend(&self, elem: T) -> List<T> {
+                List { head: Some(Ptr::new(Node { elem, next: self.head.clone() })) }
+            }
+
+            // Everything below the top element, sharing its nodes with this list. The tail of an
+            // empty list is empty.
+            pub fn tail(&self) -> List<T> {
+                List { head: self.head.as_ref().and_then(|node| node.next.clone()) }
+            }
+
+            // The top element
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.elem)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.head.is_none()
+            }
+
+            pub fn iter(&self) -> Iter<'_, T> {
+                Iter { next: self.head.as_deref() }
+            }
+
+            // Heap memory reachable from this version: one reference-counted allocation per
+            // element. Nodes shared with other versions are counted in each of them, so adding up
+            // the footprints of several versions overstates what they hold between them.
+            pub fn memory_footprint(&self) -> crate::footprint::Footprint {
+                crate::footprint::Footprint::counted::<Node<T>>(self.iter().count())
+            }
+        }
+
+        // Cloning copies a pointer, not the elements; the clone shares every node
+        impl<T> Clone for List<T> {
+            fn clone(&self) -> Self {
+                List { head: self.head.clone() }
+            }
+        }
+
+        impl<T> Default for List<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T: std::fmt::Debug> std::fmt::Debug for List<T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<'a, T> IntoIterator for &'a List<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+
+            fn into_iter(self) -> Iter<'a, T> {
+                self.iter()
+            }
+        }
+
+        impl<T> Drop for List<T> {
+            fn drop(&mut self) {
+                // Free nodes one at a time instead of letting each one's drop recurse into the
+                // next. As soon as we reach a node that another list still points at, it and
+                // everything after it stay alive, so there is nothing more to do. into_inner
+                // rather than try_unwrap: if two threads drop the last two lists sharing a node,
+                // exactly one of them gets it back, instead of both failing and the last decrement
+                // freeing the rest of the list recursively.
+                let mut cur_link = self.head.take();
+                while let Some(node) = cur_link {
+                    match Ptr::into_inner(node) {
+                        Some(mut node) => cur_link = node.next.take(),
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        impl<'a, T> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next.map(|node| {
+                    self.next = node.next.as_deref();
+                    &node.elem
+                })
+            }
+        }
+
+        #[cfg(test)]
+        mod test {
+            use super::{List, Ptr};
+
+            #[test]
+            fn basics() {
+                let list = List::new();
+                assert_eq!(list.head(), None);
+                assert!(list.is_empty());
+
+                let list = list.prepend(1).prepend(2).prepend(3);
+                assert_eq!(list.head(), Some(&3));
+
+                let list = list.tail();
+                assert_eq!(list.head(), Some(&2));
+                let list = list.tail();
+                assert_eq!(list.head(), Some(&1));
+                let list = list.tail();
+                assert_eq!(list.head(), None);
+
+                // Make sure empty tail works
+                let list = list.tail();
+                assert_eq!(list.head(), None);
+            }
+
+            #[test]
+            fn iter() {
+                let list = List::new().prepend(1).prepend(2).prepend(3);
+                assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+                assert_eq!((&list).into_iter().count(), 3);
+                assert_eq!(format!("{:?}", list), "[3, 2, 1]");
+            }
+
+            #[test]
+            fn versions_share_nodes() {
+                let base = List::new().prepend("a").prepend("b");
+                let left = base.prepend("left");
+                let right = base.prepend("right");
+
+                // Both new versions sit on top of the very same nodes as base
+                assert!(Ptr::ptr_eq(left.tail().head.as_ref().unwrap(), base.head.as_ref().unwrap()));
+                assert!(Ptr::ptr_eq(right.tail().head.as_ref().unwrap(), base.head.as_ref().unwrap()));
+                assert_eq!(left.iter().copied().collect::<Vec<_>>(), ["left", "b", "a"]);
+                assert_eq!(right.iter().copied().collect::<Vec<_>>(), ["right", "b", "a"]);
+                assert_eq!(base.iter().copied().collect::<Vec<_>>(), ["b", "a"]);
+
+                let copy = left.clone();
+                assert!(Ptr::ptr_eq(copy.head.as_ref().unwrap(), left.head.as_ref().unwrap()));
+            }
+
+            #[test]
+            fn memory_footprint() {
+                assert_eq!(List::<u64>::new().memory_footprint().heap_bytes, 0);
+
+                let base = List::new().prepend(1u64).prepend(2);
+                let extended = base.prepend(3);
+                let footprint = extended.memory_footprint();
+                assert_eq!(footprint.nodes, 3);
+                // The two reference counts live in the same allocation as the node
+                assert_eq!(footprint.bytes_per_node, 2 * std::mem::size_of::<usize>() + std::mem::size_of::<super::Node<u64>>());
+                assert_eq!(footprint.heap_bytes, 3 * footprint.bytes_per_node);
+                assert_eq!(base.memory_footprint().nodes, 2);
+            }
+
+            #[test]
+            fn drop_stops_at_shared_nodes() {
+                let mut base = List::new();
+                for i in 0..100_000 {
+                    base = base.prepend(i);
+                }
+                let mut extended = base.clone();
+                for i in 100_000..200_000 {
+                    extended = extended.prepend(i);
+                }
+
+                // Dropping the longer version frees only its own nodes
+                let shared = base.head.clone().unwrap();
+                drop(extended);
+                assert_eq!(Ptr::strong_count(&shared), 2);
+                assert_eq!(base.iter().count(), 100_000);
+                assert_eq!(base.head(), Some(&99_999));
+
+                // And a long list on its own drops without overflowing the stack
+                drop(shared);
+                drop(base);
+            }
+        }
+    };
+}
+
+pub mod rc {
+    use std::rc::Rc as Ptr;
+
+    persistent_list!();
+}
+
+#[cfg(feature = "sync")]
+pub mod sync {
+    use std::sync::Arc as Ptr;
+
+    persistent_list!();
+
+    #[cfg(test)]
+    mod thread_test {
+        use super::List;
+        use std::sync::{Arc, Barrier};
+        use std::thread;
+
+        #[test]
+        fn versions_shared_across_threads() {
+            let base = List::new().prepend(1).prepend(2);
+            let handles: Vec<_> = (0..4).map(|i| {
+                let base = base.clone();
+                thread::spawn(move || {
+                    let mine = base.prepend(10 + i);
+                    mine.iter().sum::<i32>()
+                })
+            }).collect();
+
+            let sums: Vec<i32> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+            assert_eq!(sums, [13, 14, 15, 16]);
+            assert_eq!(base.iter().copied().collect::<Vec<_>>(), [2, 1]);
+        }
+
+        // Long versions sharing one long tail, dropped on several threads at once. Whichever
+        // thread lets go of the shared nodes last has to free them without recursing.
+        #[test]
+        fn concurrent_drops_of_shared_tail() {
+            for _ in 0..4 {
+                let mut base = List::new();
+                for i in 0..100_000 {
+                    base = base.prepend(i);
+                }
+                let barrier = Arc::new(Barrier::new(4));
+                let handles: Vec<_> = (0..4).map(|i| {
+                    let version = base.prepend(i);
+                    let barrier = Arc::clone(&barrier);
+                    thread::spawn(move || {
+                        barrier.wait();
+                        drop(version);
+                    })
+                }).collect();
+                drop(base);
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,10 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+
+use crate::dot;
+use crate::footprint::Footprint;
+
 pub struct IntoIter<T>(List<T>); // convert list into iterator
 
 // Iter is generic over some lifetime 'a
@@ -5,6 +12,21 @@ pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
 }
 
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+}
+
+// Position in a list that can edit it as it goes. The cursor points at one of the links between
+// nodes: the element just past that link is the current one, and once the cursor has walked past
+// the last element there is no current element ("past the end"). Like the nightly std LinkedList
+// cursors, but since the list is singly linked it only moves forward.
+pub struct CursorMut<'a, T> {
+    link: Option<&'a mut Link<T>>, // only ever None for a moment while the cursor moves
+    index: usize,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
 pub struct List<T> {
     head: Option<Box<Node<T>>>,
 }
@@ -44,7 +66,7 @@ impl<T> List<T> {
 
     pub fn push(&mut self, elem: T) {
         let new_node = Box::new(Node {
-            elem: elem,
+            elem,
             // references must always be valid, so if we are moving ownership of what self.head
             // currently points to to "next", we need to replace it with something else (in this
             // case, none). Here we use the take() method, which is the same as using the
@@ -64,12 +86,361 @@ impl<T> List<T> {
         })
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head.as_deref() }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { next: self.head.as_deref_mut() }
+    }
+
+    // Heap memory held by the list: one node allocation per element. The list doesn't keep track
+    // of its length, so this has to walk it to count them.
+    pub fn memory_footprint(&self) -> Footprint {
+        Footprint::boxed::<Node<T>>(self.iter().count())
+    }
+
+    // Cursor whose current element is the top of the list
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { link: Some(&mut self.head), index: 0 }
+    }
+
+    // Reverse the list in place by turning every next pointer around
+    pub fn reverse(&mut self) {
+        let mut reversed = None;
+        let mut cur_node = self.head.take();
+        while let Some(mut node) = cur_node {
+            cur_node = node.next.take();
+            node.next = reversed;
+            reversed = Some(node);
+        }
+        self.head = reversed;
+    }
+
+    // Keep only the elements for which keep returns true, in their original order
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let mut cur_link = &mut self.head;
+        while let Some(node) = cur_link.as_ref() {
+            if keep(&node.elem) {
+                cur_link = &mut cur_link.as_mut().unwrap().next;
+            } else {
+                // Unlink the node; cur_link now points at whatever came after it
+                let mut node = cur_link.take().unwrap();
+                *cur_link = node.next.take();
+            }
+        }
+    }
+
+    // Split the list in two at index at (counting from the top, which is index 0). self keeps the
+    // first at elements and the rest are returned. Panics if at is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> List<T> {
+        let mut cur_link = &mut self.head;
+        for _ in 0..at {
+            cur_link = &mut cur_link.as_mut().expect("split_off index out of bounds").next;
+        }
+        List { head: cur_link.take() }
+    }
+
+    // Move all of other's elements to the bottom of this list, in their current order, leaving
+    // other empty. Takes time proportional to the length of self, not other.
+    pub fn append(&mut self, other: &mut List<T>) {
+        let mut tail = &mut self.head;
+        while let Some(node) = tail {
+            tail = &mut node.next;
+        }
+        *tail = other.head.take();
+    }
+
+    // Sort the list with a stable merge sort, relinking the existing nodes rather than moving
+    // elements or allocating new nodes. Bottom-up rather than recursive: runs of 1, 2, 4, ...
+    // nodes are kept in bins like the digits of a binary counter, and each new node is carried
+    // up through the full bins by merging, so there are never more than log2(n) runs waiting.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        // bins[i] is either empty or a sorted run of 2^i nodes. Higher bins always hold nodes
+        // that came earlier in the list, which is what keeps the sort stable.
+        let mut bins: Vec<List<T>> = Vec::new();
+        while let Some(mut node) = self.head.take() {
+            self.head = node.next.take();
+            let mut run = List { head: Some(node) };
+
+            let mut i = 0;
+            while i < bins.len() && bins[i].head.is_some() {
+                let earlier = std::mem::take(&mut bins[i]);
+                run = merge(earlier, run, &mut compare);
+                i += 1;
+            }
+            if i == bins.len() {
+                bins.push(List::new());
+            }
+            bins[i] = run;
+        }
+
+        // Whatever is left in the bins gets merged from the latest nodes (lowest bin) up
+        let mut sorted = List::new();
+        for earlier in bins {
+            sorted = merge(earlier, sorted, &mut compare);
+        }
+        *self = sorted;
+    }
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    fn link(&mut self) -> &mut Link<T> {
+        self.link.as_deref_mut().unwrap()
+    }
+
+    // Index of the current element from the top, or None past the end
+    pub fn index(&self) -> Option<usize> {
+        self.link.as_ref().unwrap().as_ref().map(|_| self.index)
+    }
+
+    // Move on to the next element. Past the end this does nothing.
+    pub fn move_next(&mut self) {
+        let link = self.link.take().unwrap();
+        if link.is_some() {
+            self.link = Some(&mut link.as_mut().unwrap().next);
+            self.index += 1;
+        } else {
+            self.link = Some(link);
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.link().as_mut().map(|node| &mut node.elem)
+    }
+
+    // The element after the current one
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        self.link().as_mut()?.next.as_mut().map(|node| &mut node.elem)
+    }
+
+    // Swap elem in for the current element, returning the old one. Past the end there is nothing
+    // to replace, and elem is handed back as the error.
+    pub fn replace_current(&mut self, elem: T) -> Result<T, T> {
+        match self.current() {
+            Some(current) => Ok(std::mem::replace(current, elem)),
+            None => Err(elem),
+        }
+    }
+
+    // Insert elem just before the current element (or on the end of the list, if the cursor is
+    // past the end). The cursor stays on the same element, whose index goes up by one.
+    pub fn insert_before(&mut self, elem: T) {
+        let link = self.link.take().unwrap();
+        let next = link.take();
+        let node = link.insert(Box::new(Node { elem, next }));
+        self.link = Some(&mut node.next);
+        self.index += 1;
+    }
+
+    // Insert elem just after the current element, without moving the cursor. Past the end there
+    // is nothing to go after, so elem goes on the end of the list, the same as insert_before.
+    pub fn insert_after(&mut self, elem: T) {
+        match self.link().as_mut() {
+            Some(node) => node.next = Some(Box::new(Node { elem, next: node.next.take() })),
+            None => self.insert_before(elem),
+        }
+    }
+
+    // Unlink the current element and return it. The element after it becomes the current one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let link = self.link();
+        link.take().map(|mut node| {
+            *link = node.next.take();
+            node.elem
+        })
+    }
+
+    // Move all of other's elements in just before the current element, in their current order.
+    // The cursor stays on the same element.
+    pub fn splice_before(&mut self, other: List<T>) {
+        let link = self.link.take().unwrap();
+        let (tail, count) = splice(link, other);
+        self.link = Some(tail);
+        self.index += count;
+    }
+
+    // Move all of other's elements in just after the current element, in their current order,
+    // without moving the cursor. Past the end they go on the end of the list.
+    pub fn splice_after(&mut self, other: List<T>) {
+        match self.link().as_mut() {
+            Some(node) => {
+                splice(&mut node.next, other);
+            }
+            None => self.splice_before(other),
+        }
+    }
+}
+
+// Link other's nodes in at link, ahead of whatever was there. Returns the link after the last
+// node spliced in (now holding what used to be at link) and the number of nodes spliced in.
+fn splice<T>(link: &mut Link<T>, mut other: List<T>) -> (&mut Link<T>, usize) {
+    let rest = link.take();
+    *link = other.head.take();
+
+    let mut tail = link;
+    let mut count = 0;
+    while let Some(node) = tail {
+        tail = &mut node.next;
+        count += 1;
+    }
+    *tail = rest;
+    (tail, count)
+}
+
+impl<T: Ord> List<T> {
+    // Sort into increasing order from the top down (see sort_by)
+    pub fn sort(&mut self) {
+        self.sort_by(T::cmp);
+    }
+}
+
+// Merge two sorted lists into one by relinking their nodes. On ties the node from earlier wins,
+// so merging runs in their original order is stable.
+fn merge<T, F: FnMut(&T, &T) -> Ordering>(mut earlier: List<T>, mut later: List<T>, compare: &mut F) -> List<T> {
+    let mut merged = List::new();
+    let mut tail = &mut merged.head;
+    while let (Some(a), Some(b)) = (&earlier.head, &later.head) {
+        let from_earlier = compare(&b.elem, &a.elem) != Ordering::Less;
+        let source = if from_earlier { &mut earlier } else { &mut later };
+        let mut node = source.head.take().unwrap();
+        source.head = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+
+    // One side has run out; the other is already sorted and can be attached as it is
+    *tail = if earlier.head.is_some() { earlier.head.take() } else { later.head.take() };
+    merged
+}
+
+impl<T: Display> List<T> {
+    // The list's nodes and next pointers as a Graphviz DOT graph (see dot::render)
+    pub fn to_dot(&self) -> String {
+        dot::render("second::List", self.iter())
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Elements from the top of the stack down, as `a -> b -> end`
+impl<T: Display> Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for elem in self.iter() {
+            write!(f, "{} -> ", elem)?;
+        }
+        write!(f, "end")
+    }
+}
+
+// Serialized as a sequence from the top of the stack down, so a deserialized list pops in the same
+// order as the original
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for List<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for List<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // The bottom of the stack has to be pushed first
+        let elems = Vec::<T>::deserialize(deserializer)?;
+        let mut list = List::new();
+        for elem in elems.into_iter().rev() {
+            list.push(elem);
+        }
+        Ok(list)
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Copies keep the same order, top to bottom
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut list = List::new();
+        // Append at the bottom as we go, rather than pushing and ending up reversed
+        let mut tail = &mut list.head;
+        for elem in self.iter() {
+            tail = &mut tail.insert(Box::new(Node { elem: elem.clone(), next: None })).next;
+        }
+        list
+    }
+}
+
+// Lists are equal if they hold equal elements in the same order
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Include the length so that lists of lists that only differ in where one list ends and
+        // the next begins hash differently
+        let mut len = 0;
+        for elem in self.iter() {
+            elem.hash(state);
+            len += 1;
+        }
+        state.write_usize(len);
+    }
+}
+
+// Pushes each element in turn, the same as calling push() in a loop, so the last element of the
+// iterator ends up on top
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
         IntoIter(self)
     }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
 
-    pub fn iter(&self) -> Iter<T> {
-        Iter { next: self.head.as_deref().map(|node| &*node) }
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
     }
 }
 
@@ -97,13 +468,26 @@ impl<'a, T> Iterator for Iter<'a, T> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.map(|node| {
-            self.next = node.next.as_deref().map(|node| &*node);
+            self.next = node.next.as_deref();
             &node.elem
         })
     }
 }
 
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // take() the reference out, since a &mut can't be copied the way Iter copies its &
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            &mut node.elem
+        })
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::option_map_unit_fn)]
 mod test{
     use super::List;
 
@@ -183,4 +567,438 @@ mod test{
         assert_eq!(iter.next(), Some(&2));
         assert_eq!(iter.next(), Some(&1));
     }
+
+    #[test]
+    fn formatting() {
+        let mut list = List::new();
+        assert_eq!(list.to_string(), "end");
+        assert_eq!(format!("{:?}", list), "[]");
+
+        list.push("a"); list.push("b"); list.push("c");
+        assert_eq!(list.to_string(), "c -> b -> a -> end");
+        assert_eq!(format!("{:?}", list), "[\"c\", \"b\", \"a\"]");
+
+        let dot = list.to_dot();
+        assert!(dot.starts_with("digraph \"second::List\" {"));
+        assert!(dot.contains("n1 [label=\"{ b | <next> }\"];"));
+        assert!(dot.contains("n0:next:c -> n1;"));
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let mut list = List::new();
+        assert_eq!(list.memory_footprint().heap_bytes, 0);
+
+        list.push(1u64); list.push(2); list.push(3);
+        list.pop();
+        let footprint = list.memory_footprint();
+        assert_eq!(footprint.nodes, 2);
+        assert_eq!(footprint.bytes_per_node, std::mem::size_of::<super::Node<u64>>());
+        assert_eq!(footprint.heap_bytes, 2 * footprint.bytes_per_node);
+    }
+
+    #[test]
+    fn default() {
+        let list: List<i32> = List::default();
+        assert_eq!(list.peek(), None);
+    }
+
+    #[test]
+    fn clone() {
+        let mut list = List::new();
+        list.push(1); list.push(2); list.push(3);
+
+        let mut copy = list.clone();
+        assert_eq!(copy.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+
+        // The copy is independent of the original
+        copy.pop();
+        copy.push(4);
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&3, &2, &1]);
+        assert_eq!(copy.iter().collect::<Vec<_>>(), [&4, &2, &1]);
+        assert_eq!(List::<i32>::new().clone().peek(), None);
+    }
+
+    #[test]
+    fn equality() {
+        let a: List<i32> = [1, 2, 3].into_iter().collect();
+        let b: List<i32> = [1, 2, 3].into_iter().collect();
+        let reversed: List<i32> = [3, 2, 1].into_iter().collect();
+        let shorter: List<i32> = [2, 3].into_iter().collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, reversed);
+        assert_ne!(a, shorter);
+        assert_ne!(shorter, a);
+        assert_eq!(List::<i32>::new(), List::new());
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of<T: Hash>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let a: List<i32> = [1, 2, 3].into_iter().collect();
+        let b = a.clone();
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert_ne!(hash_of(&a), hash_of(&[3, 2, 1].into_iter().collect::<List<i32>>()));
+
+        // Where one list stops and the next starts matters
+        let split: List<List<i32>> = [
+            [1].into_iter().collect(),
+            [2, 3].into_iter().collect(),
+        ].into_iter().collect();
+        let other_split: List<List<i32>> = [
+            [1, 2].into_iter().collect(),
+            [3].into_iter().collect(),
+        ].into_iter().collect();
+        assert_ne!(hash_of(&split), hash_of(&other_split));
+
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(a));
+        assert!(!set.insert(b));
+    }
+
+    #[test]
+    fn from_iter_and_extend() {
+        // Same as pushing each element, so the last one is on top
+        let mut list: List<i32> = (1..=3).collect();
+        assert_eq!(list.peek(), Some(&3));
+
+        list.extend(vec![4, 5]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [5, 4, 3, 2, 1]);
+
+        list.extend(std::iter::empty());
+        assert_eq!(list.pop(), Some(5));
+    }
+
+    #[test]
+    fn for_loops() {
+        let mut list = List::new();
+        list.push(1); list.push(2); list.push(3);
+
+        let mut borrowed = Vec::new();
+        for elem in &list {
+            borrowed.push(*elem);
+        }
+        assert_eq!(borrowed, [3, 2, 1]);
+
+        let mut owned = Vec::new();
+        for elem in list {
+            owned.push(elem);
+        }
+        assert_eq!(owned, [3, 2, 1]);
+    }
+
+    fn contents<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    // List whose elements read top to bottom in the same order as elems
+    fn from_top<T>(elems: Vec<T>) -> List<T> {
+        elems.into_iter().rev().collect()
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = from_top(vec![1, 2, 3]);
+        for elem in list.iter_mut() {
+            *elem *= 10;
+        }
+        assert_eq!(contents(&list), [10, 20, 30]);
+
+        for elem in &mut list {
+            *elem += 1;
+        }
+        assert_eq!(contents(&list), [11, 21, 31]);
+
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.next(), Some(&mut 11));
+        assert_eq!(iter.next(), Some(&mut 21));
+        assert_eq!(iter.next(), Some(&mut 31));
+        assert_eq!(iter.next(), None);
+        assert_eq!(List::<i32>::new().iter_mut().next(), None);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut list = from_top(vec![1, 2, 3, 4]);
+        list.reverse();
+        assert_eq!(contents(&list), [4, 3, 2, 1]);
+        assert_eq!(list.pop(), Some(4));
+
+        let mut empty = List::<i32>::new();
+        empty.reverse();
+        assert_eq!(empty.peek(), None);
+
+        let mut single = from_top(vec![7]);
+        single.reverse();
+        assert_eq!(contents(&single), [7]);
+    }
+
+    #[test]
+    fn retain() {
+        let mut list = from_top((1..=10).collect());
+        list.retain(|elem| elem % 3 != 0);
+        assert_eq!(contents(&list), [1, 2, 4, 5, 7, 8, 10]);
+
+        // Removing the top, the bottom and runs of neighbours
+        list.retain(|&elem| elem != 1 && elem != 10 && elem != 4 && elem != 5);
+        assert_eq!(contents(&list), [2, 7, 8]);
+
+        list.retain(|_| true);
+        assert_eq!(contents(&list), [2, 7, 8]);
+        list.retain(|_| false);
+        assert_eq!(list.peek(), None);
+    }
+
+    #[test]
+    fn split_off() {
+        let mut list = from_top(vec![1, 2, 3, 4, 5]);
+        let rest = list.split_off(2);
+        assert_eq!(contents(&list), [1, 2]);
+        assert_eq!(contents(&rest), [3, 4, 5]);
+
+        let all = list.split_off(0);
+        assert_eq!(list.peek(), None);
+        assert_eq!(contents(&all), [1, 2]);
+
+        let mut list = from_top(vec![1, 2]);
+        let none = list.split_off(2);
+        assert_eq!(contents(&list), [1, 2]);
+        assert_eq!(none.peek(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "split_off index out of bounds")]
+    fn split_off_past_end() {
+        from_top(vec![1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn append() {
+        let mut list = from_top(vec![1, 2]);
+        let mut other = from_top(vec![3, 4]);
+        list.append(&mut other);
+        assert_eq!(contents(&list), [1, 2, 3, 4]);
+        assert_eq!(other.peek(), None);
+
+        // Appending an empty list, and appending to one
+        list.append(&mut other);
+        assert_eq!(contents(&list), [1, 2, 3, 4]);
+        other.append(&mut list);
+        assert_eq!(contents(&other), [1, 2, 3, 4]);
+        assert_eq!(list.peek(), None);
+    }
+
+    #[test]
+    fn sort() {
+        let mut list = from_top(vec![5, 1, 4, 2, 3, 2]);
+        list.sort();
+        assert_eq!(contents(&list), [1, 2, 2, 3, 4, 5]);
+
+        let mut list = from_top(vec![1, 2, 3]);
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(contents(&list), [3, 2, 1]);
+
+        let mut empty = List::<i32>::new();
+        empty.sort();
+        assert_eq!(empty.peek(), None);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        // Sorting by key alone must keep equal keys in their original order
+        let elems: Vec<(i32, usize)> = (0..200).map(|i| ((i * 7) % 5, i as usize)).collect();
+        let mut list = from_top(elems.clone());
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut expected = elems;
+        expected.sort_by_key(|elem| elem.0);
+        assert_eq!(contents(&list), expected);
+    }
+
+    #[test]
+    fn sort_matches_vec() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for len in [1, 2, 3, 7, 8, 9, 100, 1000, 1023, 1025] {
+            let elems: Vec<i32> = (0..len).map(|_| rng.gen_range(0..50)).collect();
+            let mut list = from_top(elems.clone());
+            list.sort();
+
+            let mut expected = elems;
+            expected.sort();
+            assert_eq!(contents(&list), expected, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn sort_relinks_nodes() {
+        // The same node allocations should come out the other end, just in a different order
+        let mut list = from_top(vec![3, 1, 2]);
+        let mut addresses: Vec<*const i32> = list.iter().map(|elem| elem as *const i32).collect();
+        list.sort();
+        let mut after: Vec<*const i32> = list.iter().map(|elem| elem as *const i32).collect();
+        addresses.sort();
+        after.sort();
+        assert_eq!(addresses, after);
+    }
+
+    // None of the operations recurse, so they cope with lists far longer than the stack is deep
+    #[test]
+    fn long_lists() {
+        let mut list: List<i32> = (0..200_000).collect();
+        list.reverse();
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(list.peek(), Some(&199_999));
+        list.retain(|elem| elem % 2 == 0);
+        let mut rest = list.split_off(50_000);
+        list.append(&mut rest);
+        assert_eq!(list.iter_mut().count(), 100_000);
+    }
+
+    #[test]
+    fn cursor_walk() {
+        let mut list = from_top(vec![1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.peek_next(), None);
+
+        // Past the end, and staying there
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+
+        let mut empty = List::<i32>::new();
+        let mut cursor = empty.cursor_front_mut();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), None);
+    }
+
+    #[test]
+    fn cursor_replace() {
+        let mut list = from_top(vec![1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.replace_current(20), Ok(2));
+        *cursor.current().unwrap() += 1;
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.replace_current(40), Err(40));
+        assert_eq!(contents(&list), [1, 21, 3]);
+    }
+
+    #[test]
+    fn cursor_insert() {
+        let mut list = from_top(vec![1, 3]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_before(0);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.index(), Some(1));
+
+        cursor.insert_after(2);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+
+        cursor.move_next();
+        cursor.move_next();
+        cursor.insert_after(4);
+        cursor.move_next();
+        cursor.move_next();
+        // Both kinds of insert go on the end once past it
+        cursor.insert_before(5);
+        cursor.insert_after(6);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(contents(&list), [0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn cursor_remove() {
+        let mut list = from_top(vec![1, 2, 3, 4]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.remove_current(), None);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(contents(&list), [2]);
+
+        // Removing every element, using the cursor as a filter
+        let mut list = from_top((1..=10).collect());
+        let mut cursor = list.cursor_front_mut();
+        while let Some(elem) = cursor.current() {
+            if *elem % 2 == 0 {
+                cursor.remove_current();
+            } else {
+                cursor.move_next();
+            }
+        }
+        assert_eq!(contents(&list), [1, 3, 5, 7, 9]);
+    }
+
+    #[test]
+    fn cursor_splice() {
+        let mut list = from_top(vec![1, 5]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after(from_top(vec![2, 3]));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 5));
+        cursor.splice_before(from_top(vec![4]));
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.index(), Some(4));
+
+        // Empty lists change nothing; past the end both splices go on the end
+        cursor.splice_before(List::new());
+        cursor.splice_after(List::new());
+        cursor.move_next();
+        cursor.splice_after(from_top(vec![6]));
+        cursor.splice_before(from_top(vec![7, 8]));
+        assert_eq!(cursor.index(), None);
+        assert_eq!(contents(&list), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let mut empty = List::new();
+        empty.cursor_front_mut().splice_before(from_top(vec![1, 2]));
+        assert_eq!(contents(&empty), [1, 2]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut list = List::new();
+        list.push("a".to_string()); list.push("b".to_string());
+
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(json, "[\"b\",\"a\"]");
+
+        let list: List<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(list.iter().collect::<Vec<_>>(), ["b", "a"]);
+
+        let empty: List<i32> = serde_json::from_str("[]").unwrap();
+        assert_eq!(empty.peek(), None);
+        assert!(serde_json::from_str::<List<i32>>("{\"head\": 1}").is_err());
+    }
 }
@@ -0,0 +1,529 @@
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use crate::footprint::Footprint;
+use crate::sorted_set::{after_end, SortedSet};
+
+// Sorted set with the same semantics as cs120::List (each element appears at most once, iteration
+// is in sorted order), but with expected O(log n) insert/delete/contains. Each node sits on a
+// random number of levels; level 0 links every node, and each level above skips over roughly half
+// of the nodes of the level below, so searches can start at the top and drop down as they go.
+//
+// Nodes live in a Vec and refer to each other by index, which avoids the need for shared
+// ownership between the levels. Slots freed by delete are reused by later inserts.
+
+const MAX_LEVEL: usize = 32; // plenty for any list that fits in memory
+
+pub struct IntoIter<T, R>(SkipList<T, R>);
+
+pub struct Iter<'a, T> {
+    nodes: &'a [Option<Node<T>>],
+    next: Option<usize>,
+}
+
+// Elements within a range. The start of the range is found with a normal top-down search; after
+// that we walk level 0 until we pass the end.
+pub struct Range<'a, T, R> {
+    iter: Iter<'a, T>,
+    bounds: R,
+}
+
+struct Node<T> {
+    elem: T,
+    next: Vec<Option<usize>>, // one forward link per level this node is on
+}
+
+pub struct SkipList<T, R = StdRng> {
+    head: Vec<Option<usize>>,    // first node on each level
+    nodes: Vec<Option<Node<T>>>, // None marks a free slot
+    free: Vec<usize>,            // indices of free slots
+    len: usize,
+    rng: R,                      // decides how many levels each new node is on
+}
+
+impl<T: Ord> SkipList<T> {
+    pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+}
+
+impl<T: Ord, R: RngCore> SkipList<T, R> {
+    // Empty list that uses rng to choose node levels. Passing a seeded generator makes the shape of
+    // the list (and so its performance) reproducible.
+    pub fn with_rng(rng: R) -> Self {
+        SkipList { head: Vec::new(), nodes: Vec::new(), free: Vec::new(), len: 0, rng }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Number of levels currently in use
+    pub fn levels(&self) -> usize {
+        self.head.len()
+    }
+
+    // Heap memory held by the list: its slots (including free ones), each node's Vec of links, the
+    // head links and the free list. bytes_per_node is the slot alone; a node on k levels also
+    // holds k links on the heap.
+    pub fn memory_footprint(&self) -> Footprint {
+        let link = mem::size_of::<Option<usize>>();
+        let bytes_per_node = mem::size_of::<Option<Node<T>>>();
+        let node_links: usize = self.nodes.iter().flatten().map(|node| node.next.capacity() * link).sum();
+        Footprint {
+            nodes: self.nodes.len(),
+            bytes_per_node,
+            heap_bytes: self.nodes.capacity() * bytes_per_node
+                + node_links
+                + self.head.capacity() * link
+                + self.free.capacity() * mem::size_of::<usize>(),
+        }
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.head.first().copied().flatten().map(|index| &self.node(index).elem)
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        match self.first_at_least(elem) {
+            Some(index) => self.node(index).elem == *elem,
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { nodes: &self.nodes, next: self.head.first().copied().flatten() }
+    }
+
+    // Iterate over the elements within range, in sorted order. A range whose start lies after its
+    // end is simply empty.
+    pub fn range<B: RangeBounds<T>>(&self, range: B) -> Range<'_, T, B> {
+        let next = match range.start_bound() {
+            Bound::Unbounded => self.link(None, 0),
+            Bound::Included(start) => self.first_at_least(start),
+            Bound::Excluded(start) => {
+                // First element >= start, skipping it if it is start itself
+                let next = self.first_at_least(start);
+                match next {
+                    Some(index) if self.node(index).elem == *start => self.link(next, 0),
+                    _ => next,
+                }
+            }
+        };
+
+        Range { iter: Iter { nodes: &self.nodes, next }, bounds: range }
+    }
+
+    // Insert elem, returning false if it was already present
+    pub fn insert(&mut self, elem: T) -> bool {
+        let mut update = self.find(&elem);
+        let prev = update.first().copied().flatten();
+        if let Some(index) = self.link(prev, 0) {
+            if self.node(index).elem == elem {
+                return false;
+            }
+        }
+
+        // New levels start out empty, so the node is linked straight from the head on those
+        let level = self.random_level();
+        while self.head.len() < level {
+            self.head.push(None);
+            update.push(None);
+        }
+
+        let next = (0..level).map(|lvl| self.link(update[lvl], lvl)).collect();
+        let index = self.alloc(Node { elem, next });
+        for (lvl, prev) in update.iter().take(level).enumerate() {
+            self.set_link(*prev, lvl, Some(index));
+        }
+
+        self.len += 1;
+        true
+    }
+
+    // Remove elem, returning false if it was not present
+    pub fn delete(&mut self, elem: &T) -> bool {
+        let update = self.find(elem);
+        let index = match self.link(update.first().copied().flatten(), 0) {
+            Some(index) if self.node(index).elem == *elem => index,
+            _ => return false,
+        };
+
+        // On every level the node is on, its predecessor is the one we found on the way down
+        let node = self.nodes[index].take().expect("linked index refers to a free slot");
+        for (lvl, next) in node.next.into_iter().enumerate() {
+            self.set_link(update[lvl], lvl, next);
+        }
+        self.free.push(index);
+
+        // Drop any levels that no longer have nodes on them
+        while let Some(None) = self.head.last() {
+            self.head.pop();
+        }
+
+        self.len -= 1;
+        true
+    }
+
+    // For each level (bottom first), the last node whose element is less than elem, or None if
+    // there is no such node and the search on that level starts from the head
+    fn find(&self, elem: &T) -> Vec<Option<usize>> {
+        let mut update = vec![None; self.head.len()];
+        let mut cur = None;
+        for lvl in (0..self.head.len()).rev() {
+            while let Some(next) = self.link(cur, lvl) {
+                if self.node(next).elem >= *elem {
+                    break;
+                }
+                cur = Some(next);
+            }
+            update[lvl] = cur;
+        }
+        update
+    }
+
+    // Node holding the smallest element >= elem
+    fn first_at_least(&self, elem: &T) -> Option<usize> {
+        self.link(self.find(elem).first().copied().flatten(), 0)
+    }
+
+    // Each extra level is taken with probability 1/2, so a node is on k levels with probability
+    // 1/2^k. One call to the generator gives us up to 32 coin flips.
+    fn random_level(&mut self) -> usize {
+        let flips = self.rng.next_u32();
+        (flips.trailing_ones() as usize + 1).min(MAX_LEVEL)
+    }
+}
+
+impl<T, R> SkipList<T, R> {
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index].as_ref().expect("linked index refers to a free slot")
+    }
+
+    // Link following `from` (a node index, or None for the head) on the given level
+    fn link(&self, from: Option<usize>, lvl: usize) -> Option<usize> {
+        match from {
+            Some(index) => self.node(index).next[lvl],
+            None => self.head.get(lvl).copied().flatten(),
+        }
+    }
+
+    fn set_link(&mut self, from: Option<usize>, lvl: usize, to: Option<usize>) {
+        match from {
+            Some(index) => {
+                self.nodes[index].as_mut().expect("linked index refers to a free slot").next[lvl] = to
+            }
+            None => self.head[lvl] = to,
+        }
+    }
+
+    // Store node in a free slot if there is one, or at the end otherwise
+    fn alloc(&mut self, node: Node<T>) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+}
+
+impl<T: Ord> Default for SkipList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord, R: RngCore> SortedSet<T> for SkipList<T, R> {
+    type Iter<'a> = Iter<'a, T> where T: 'a, R: 'a;
+    type Range<'a, B> = Range<'a, T, B> where T: 'a, R: 'a, B: RangeBounds<T>;
+
+    fn insert(&mut self, elem: T) -> bool {
+        SkipList::insert(self, elem)
+    }
+
+    fn delete(&mut self, elem: &T) -> bool {
+        SkipList::delete(self, elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        SkipList::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        SkipList::iter(self)
+    }
+
+    fn range<B: RangeBounds<T>>(&self, range: B) -> Range<'_, T, B> {
+        SkipList::range(self, range)
+    }
+}
+
+impl<T, R> IntoIterator for SkipList<T, R> {
+    type Item = T;
+    type IntoIter = IntoIter<T, R>;
+
+    fn into_iter(self) -> IntoIter<T, R> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T: Ord, R: RngCore> IntoIterator for &'a SkipList<T, R> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, R> Iterator for IntoIter<T, R> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Walk level 0, taking each node out of its slot. The higher levels are left dangling,
+        // which is fine since nothing else can use the list any more.
+        let list = &mut self.0;
+        let index = list.head.first().copied().flatten()?;
+        let node = list.nodes[index].take().expect("linked index refers to a free slot");
+        list.head[0] = node.next[0];
+        list.len -= 1;
+        Some(node.elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|index| {
+            let node = self.nodes[index].as_ref().expect("linked index refers to a free slot");
+            self.next = node.next[0];
+            &node.elem
+        })
+    }
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Range<'a, T, R> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let elem = self.iter.next()?;
+        if after_end(self.bounds.end_bound(), elem) {
+            // Everything after this is larger still, so we are done
+            self.iter.next = None;
+            return None;
+        }
+        Some(elem)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod test {
+    use super::SkipList;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeSet;
+
+    fn seeded<T: Ord>() -> SkipList<T, StdRng> {
+        SkipList::with_rng(StdRng::seed_from_u64(42))
+    }
+
+    // Shape of each level, top first, so tests can compare lists built with the same seed
+    fn levels<T: Ord + Clone, R: rand::RngCore>(list: &SkipList<T, R>) -> Vec<Vec<T>> {
+        let mut levels = Vec::new();
+        for lvl in (0..list.levels()).rev() {
+            let mut level = Vec::new();
+            let mut cur = list.link(None, lvl);
+            while let Some(index) = cur {
+                level.push(list.node(index).elem.clone());
+                cur = list.link(cur, lvl);
+            }
+            levels.push(level);
+        }
+        levels
+    }
+
+    #[test]
+    fn basics() {
+        let mut list = seeded();
+
+        // Same scenario as the cs120 basics test
+        assert_eq!(list.insert(10), true);
+        assert_eq!(list.insert(10), false);
+        assert_eq!(list.insert(20), true);
+        assert_eq!(list.insert(9), true);
+        assert_eq!(list.insert(8), true);
+        assert_eq!(list.insert(11), true);
+        assert_eq!(list.insert(12), true);
+        assert_eq!(list.insert(21), true);
+        assert_eq!(list.insert(9), false);
+        assert_eq!(list.insert(8), false);
+        assert_eq!(list.insert(21), false);
+        assert_eq!(list.insert(7), true);
+        assert_eq!(list.insert(22), true);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [7, 8, 9, 10, 11, 12, 20, 21, 22]);
+        assert_eq!(list.len(), 9);
+
+        assert_eq!(list.delete(&7), true);
+        assert_eq!(list.delete(&22), true);
+        assert_eq!(list.delete(&11), true);
+        assert_eq!(list.delete(&12), true);
+        assert_eq!(list.delete(&7), false);
+        assert_eq!(list.delete(&22), false);
+        assert_eq!(list.delete(&12), false);
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [8, 9, 10, 20, 21]);
+        assert_eq!(list.len(), 5);
+
+        let mut list2 = seeded();
+        assert_eq!(list2.delete(&1), false);
+        assert_eq!(list2.insert(1), true);
+        assert_eq!(list2.delete(&1), true);
+        assert_eq!(list2.is_empty(), true);
+        assert_eq!(list2.levels(), 0);
+        assert_eq!(list2.insert(1), true);
+        assert_eq!(list2.insert(2), true);
+        assert_eq!(list2.delete(&1), true);
+        assert_eq!(list2.delete(&1), false);
+        assert_eq!(list2.first(), Some(&2));
+    }
+
+    #[test]
+    fn contains() {
+        let mut list = seeded();
+        assert_eq!(list.contains(&1), false);
+
+        for elem in [30, 10, 20] {
+            list.insert(elem);
+        }
+        for elem in [10, 20, 30] {
+            assert_eq!(list.contains(&elem), true);
+        }
+        for elem in [5, 15, 25, 35] {
+            assert_eq!(list.contains(&elem), false);
+        }
+    }
+
+    #[test]
+    fn matches_btreeset() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut list = seeded();
+        let mut set = BTreeSet::new();
+
+        for _ in 0..5000 {
+            let value = rng.gen_range(0..200);
+            match rng.gen_range(0..3) {
+                0 => assert_eq!(list.insert(value), set.insert(value)),
+                1 => assert_eq!(list.delete(&value), set.remove(&value)),
+                _ => assert_eq!(list.contains(&value), set.contains(&value)),
+            }
+            assert_eq!(list.len(), set.len());
+        }
+
+        assert!(list.iter().eq(set.iter()));
+        assert!(list.into_iter().eq(set.into_iter()));
+    }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let mut a = SkipList::with_rng(StdRng::seed_from_u64(7));
+        let mut b = SkipList::with_rng(StdRng::seed_from_u64(7));
+        for elem in (0..100).rev() {
+            a.insert(elem);
+            b.insert(elem);
+        }
+        assert_eq!(levels(&a), levels(&b));
+
+        // Every level is a sorted subset of the one below it
+        let shape = levels(&a);
+        for pair in shape.windows(2) {
+            let (upper, lower) = (&pair[0], &pair[1]);
+            assert!(upper.windows(2).all(|w| w[0] < w[1]));
+            assert!(upper.iter().all(|elem| lower.contains(elem)));
+        }
+        assert_eq!(shape.last().unwrap().len(), 100);
+    }
+
+    #[test]
+    fn levels_stay_logarithmic() {
+        let mut list = seeded();
+        for elem in 0..4096 {
+            list.insert(elem);
+        }
+
+        // log2(4096) = 12; with a fixed seed this is deterministic, but allow some slack anyway
+        assert!(list.levels() >= 8 && list.levels() <= 24, "levels = {}", list.levels());
+    }
+
+    #[test]
+    fn reuses_free_slots() {
+        let mut list = seeded();
+        for elem in 0..100 {
+            list.insert(elem);
+        }
+        for round in 0..10 {
+            for elem in 0..100 {
+                list.delete(&elem);
+            }
+            for elem in 0..100 {
+                list.insert(elem + round);
+            }
+        }
+        assert_eq!(list.nodes.len(), 100);
+        assert_eq!(list.len(), 100);
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let mut list = seeded();
+        assert_eq!(list.memory_footprint().heap_bytes, 0);
+
+        for elem in 0..100 {
+            list.insert(elem);
+        }
+        list.delete(&50);
+
+        // Every node is on at least one level, so its links add at least one link's worth
+        let footprint = list.memory_footprint();
+        let link = std::mem::size_of::<Option<usize>>();
+        assert_eq!(footprint.nodes, 100);
+        assert!(footprint.heap_bytes >= 100 * footprint.bytes_per_node + 99 * link);
+    }
+
+    #[test]
+    fn strings() {
+        let mut list = seeded();
+        for word in ["pear", "apple", "fig", "apple"] {
+            list.insert(word.to_string());
+        }
+        assert_eq!(list.delete(&"fig".to_string()), true);
+        let words: Vec<String> = list.into_iter().collect();
+        assert_eq!(words, ["apple", "pear"]);
+    }
+
+    #[test]
+    fn conformance() {
+        crate::sorted_set::conformance::run(seeded);
+    }
+}
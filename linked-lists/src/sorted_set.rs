@@ -0,0 +1,231 @@
+use std::collections::{btree_set, BTreeSet};
+use std::iter::Flatten;
+use std::ops::{Bound, RangeBounds};
+use std::option;
+
+// Operations shared by every sorted set in this crate (cs120::List, arena::SortedList,
+// skiplist::SkipList, sorted_vec::SortedVec), so that drivers and tests can be written once and run
+// against any of them. Elements are unique and iteration is always in increasing order.
+pub trait SortedSet<T: Ord> {
+    type Iter<'a>: Iterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    type Range<'a, R>: Iterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a,
+        R: RangeBounds<T>;
+
+    // Insert elem, returning false if it was already present
+    fn insert(&mut self, elem: T) -> bool;
+
+    // Remove elem, returning false if it was not present
+    fn delete(&mut self, elem: &T) -> bool;
+
+    fn contains(&self, elem: &T) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // All elements, in increasing order
+    fn iter(&self) -> Self::Iter<'_>;
+
+    // Elements within range, in increasing order. A range whose start lies after its end is empty.
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Self::Range<'_, R>;
+}
+
+// Whether elem comes before the start of a range
+pub(crate) fn before_start<T: Ord>(start: Bound<&T>, elem: &T) -> bool {
+    match start {
+        Bound::Included(start) => elem < start,
+        Bound::Excluded(start) => elem <= start,
+        Bound::Unbounded => false,
+    }
+}
+
+// Whether elem comes after the end of a range
+pub(crate) fn after_end<T: Ord>(end: Bound<&T>, elem: &T) -> bool {
+    match end {
+        Bound::Included(end) => elem > end,
+        Bound::Excluded(end) => elem >= end,
+        Bound::Unbounded => false,
+    }
+}
+
+// Whether a range can't contain anything because its start lies after its end (or at it, with
+// either end excluded)
+fn is_inverted<T: Ord, R: RangeBounds<T>>(range: &R) -> bool {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end))
+        | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+        _ => false,
+    }
+}
+
+// The standard library's sorted set, as a reference point for the lists. BTreeSet::range panics
+// on inverted ranges, so those are turned into empty iterators instead.
+impl<T: Ord> SortedSet<T> for BTreeSet<T> {
+    type Iter<'a> = btree_set::Iter<'a, T> where T: 'a;
+    type Range<'a, R> = Flatten<option::IntoIter<btree_set::Range<'a, T>>> where T: 'a, R: RangeBounds<T>;
+
+    fn insert(&mut self, elem: T) -> bool {
+        BTreeSet::insert(self, elem)
+    }
+
+    fn delete(&mut self, elem: &T) -> bool {
+        self.remove(elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        BTreeSet::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BTreeSet::iter(self)
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Self::Range<'_, R> {
+        if is_inverted(&range) {
+            None.into_iter().flatten()
+        } else {
+            Some(BTreeSet::range(self, range)).into_iter().flatten()
+        }
+    }
+}
+
+// Checks that any SortedSet implementation can be run through. Each implementation's test module
+// calls run() with a function that creates an empty set.
+#[cfg(test)]
+pub(crate) mod conformance {
+    use super::SortedSet;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeSet;
+    use std::ops::Bound;
+
+    pub(crate) fn run<S: SortedSet<i32>>(new: impl Fn() -> S) {
+        empty(new());
+        basics(new());
+        ranges(new());
+        matches_btreeset(new(), 1);
+        matches_btreeset(new(), 2);
+    }
+
+    fn contents<S: SortedSet<i32>>(set: &S) -> Vec<i32> {
+        set.iter().copied().collect()
+    }
+
+    fn range<S: SortedSet<i32>>(set: &S, bounds: (Bound<i32>, Bound<i32>)) -> Vec<i32> {
+        set.range(bounds).copied().collect()
+    }
+
+    fn empty<S: SortedSet<i32>>(mut set: S) {
+        assert_eq!(set.len(), 0);
+        assert!(set.is_empty());
+        assert!(!set.contains(&1));
+        assert!(!set.delete(&1));
+        assert_eq!(set.iter().next(), None);
+        assert_eq!(set.range(..).next(), None);
+    }
+
+    fn basics<S: SortedSet<i32>>(mut set: S) {
+        // Inserts at the front, back and middle, plus duplicates of each
+        for elem in [10, 20, 9, 8, 11, 12, 21, 7, 22] {
+            assert!(set.insert(elem), "insert({}) should succeed", elem);
+        }
+        for elem in [10, 7, 22, 11] {
+            assert!(!set.insert(elem), "duplicate insert({}) should fail", elem);
+        }
+        assert_eq!(contents(&set), [7, 8, 9, 10, 11, 12, 20, 21, 22]);
+        assert_eq!(set.len(), 9);
+
+        // Deletes at the front, back and middle, plus repeated and missing values
+        for elem in [7, 22, 11, 12] {
+            assert!(set.delete(&elem), "delete({}) should succeed", elem);
+        }
+        for elem in [7, 22, 12, 0, 15, 100] {
+            assert!(!set.delete(&elem), "delete({}) should fail", elem);
+        }
+        assert_eq!(contents(&set), [8, 9, 10, 20, 21]);
+        assert_eq!(set.len(), 5);
+        assert!(!set.is_empty());
+
+        for elem in [8, 10, 21] {
+            assert!(set.contains(&elem));
+        }
+        for elem in [7, 15, 22] {
+            assert!(!set.contains(&elem));
+        }
+    }
+
+    fn ranges<S: SortedSet<i32>>(mut set: S) {
+        for elem in [10, 20, 30, 40, 50] {
+            set.insert(elem);
+        }
+
+        assert_eq!(set.range(20..40).copied().collect::<Vec<_>>(), [20, 30]);
+        assert_eq!(set.range(20..=40).copied().collect::<Vec<_>>(), [20, 30, 40]);
+        assert_eq!(set.range(15..45).copied().collect::<Vec<_>>(), [20, 30, 40]);
+        assert_eq!(set.range(30..).copied().collect::<Vec<_>>(), [30, 40, 50]);
+        assert_eq!(set.range(..30).copied().collect::<Vec<_>>(), [10, 20]);
+        assert_eq!(set.range(..=30).copied().collect::<Vec<_>>(), [10, 20, 30]);
+        assert_eq!(set.range(..).count(), 5);
+
+        use Bound::{Excluded, Included, Unbounded};
+        assert_eq!(range(&set, (Excluded(20), Included(40))), [30, 40]);
+        assert_eq!(range(&set, (Excluded(20), Excluded(40))), [30]);
+        assert_eq!(range(&set, (Excluded(50), Unbounded)), Vec::<i32>::new());
+
+        // Empty, inverted and out-of-bounds ranges
+        assert_eq!(set.range(30..30).next(), None);
+        assert_eq!(set.range(31..40).next(), None);
+        assert_eq!(range(&set, (Included(40), Excluded(20))), Vec::<i32>::new());
+        assert_eq!(range(&set, (Excluded(30), Excluded(30))), Vec::<i32>::new());
+        assert_eq!(set.range(..10).next(), None);
+        assert_eq!(set.range(51..).next(), None);
+        assert_eq!(set.range(0..100).count(), 5);
+    }
+
+    // Random operations, checked against BTreeSet after every step
+    fn matches_btreeset<S: SortedSet<i32>>(mut set: S, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut model = BTreeSet::new();
+
+        for _ in 0..2000 {
+            let value = rng.gen_range(0..64);
+            match rng.gen_range(0..4) {
+                0 | 1 => assert_eq!(set.insert(value), model.insert(value)),
+                2 => assert_eq!(set.delete(&value), model.remove(&value)),
+                _ => {
+                    let hi = value + rng.gen_range(0..16);
+                    assert!(set.range(value..hi).eq(model.range(value..hi)));
+                }
+            }
+            assert_eq!(set.len(), model.len());
+            assert_eq!(set.contains(&value), model.contains(&value));
+        }
+
+        assert!(set.iter().eq(model.iter()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn btreeset_conformance() {
+        super::conformance::run(BTreeSet::new);
+    }
+}
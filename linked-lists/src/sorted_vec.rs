@@ -0,0 +1,174 @@
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::slice;
+
+use crate::footprint::Footprint;
+use crate::sorted_set::SortedSet;
+
+// Sorted set kept in a plain Vec. Lookups are binary searches, but insert/delete have to shift
+// everything after the position they touch, so this is the array-based baseline to compare the
+// linked structures against: O(log n) comparisons, O(n) moves, and no per-element allocation.
+pub struct SortedVec<T> {
+    elems: Vec<T>,
+}
+
+impl<T: Ord> SortedVec<T> {
+    pub fn new() -> Self {
+        SortedVec { elems: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elems.is_empty()
+    }
+
+    // Heap memory held by the set: one Vec buffer, including its spare capacity. There are no
+    // nodes, so each element counts as one, with no links.
+    pub fn memory_footprint(&self) -> Footprint {
+        let bytes_per_node = mem::size_of::<T>();
+        Footprint { nodes: self.elems.len(), bytes_per_node, heap_bytes: self.elems.capacity() * bytes_per_node }
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.elems.binary_search(elem).is_ok()
+    }
+
+    // Insert elem, returning false if it was already present
+    pub fn insert(&mut self, elem: T) -> bool {
+        match self.elems.binary_search(&elem) {
+            Ok(_) => false,
+            Err(index) => {
+                self.elems.insert(index, elem);
+                true
+            }
+        }
+    }
+
+    // Remove elem, returning false if it was not present
+    pub fn delete(&mut self, elem: &T) -> bool {
+        match self.elems.binary_search(elem) {
+            Ok(index) => {
+                self.elems.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.elems.iter()
+    }
+
+    // Elements within range, found by binary searching for both ends. A range whose start lies
+    // after its end is simply empty.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> slice::Iter<'_, T> {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.elems.partition_point(|elem| elem < start),
+            Bound::Excluded(start) => self.elems.partition_point(|elem| elem <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.elems.partition_point(|elem| elem <= end),
+            Bound::Excluded(end) => self.elems.partition_point(|elem| elem < end),
+            Bound::Unbounded => self.elems.len(),
+        };
+        self.elems[start..end.max(start)].iter()
+    }
+}
+
+impl<T: Ord> Default for SortedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> SortedSet<T> for SortedVec<T> {
+    type Iter<'a> = slice::Iter<'a, T> where T: 'a;
+    type Range<'a, R> = slice::Iter<'a, T> where T: 'a, R: RangeBounds<T>;
+
+    fn insert(&mut self, elem: T) -> bool {
+        SortedVec::insert(self, elem)
+    }
+
+    fn delete(&mut self, elem: &T) -> bool {
+        SortedVec::delete(self, elem)
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        SortedVec::contains(self, elem)
+    }
+
+    fn len(&self) -> usize {
+        self.elems.len()
+    }
+
+    fn iter(&self) -> slice::Iter<'_, T> {
+        self.elems.iter()
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> slice::Iter<'_, T> {
+        SortedVec::range(self, range)
+    }
+}
+
+impl<T> IntoIterator for SortedVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elems.into_iter()
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a SortedVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SortedVec;
+
+    #[test]
+    fn basics() {
+        let mut set = SortedVec::new();
+        assert!(set.insert("pear"));
+        assert!(set.insert("apple"));
+        assert!(set.insert("fig"));
+        assert!(!set.insert("apple"));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), ["apple", "fig", "pear"]);
+
+        assert!(set.delete(&"fig"));
+        assert!(!set.delete(&"fig"));
+        assert!(set.contains(&"pear"));
+        assert!(!set.contains(&"fig"));
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), ["apple", "pear"]);
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let mut set = SortedVec::new();
+        assert_eq!(set.memory_footprint().heap_bytes, 0);
+
+        for elem in 0..10u64 {
+            set.insert(elem);
+        }
+        set.delete(&3);
+        let footprint = set.memory_footprint();
+        assert_eq!(footprint.nodes, 9);
+        assert_eq!(footprint.bytes_per_node, 8);
+        assert!(footprint.heap_bytes >= 10 * 8);
+    }
+
+    #[test]
+    fn conformance() {
+        crate::sorted_set::conformance::run(SortedVec::new);
+    }
+}
@@ -0,0 +1,409 @@
+use std::hint;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+
+use crate::footprint::Footprint;
+
+// Lock-free stack (Treiber's algorithm): the same push/pop as second::List, but through a shared
+// reference, from any number of threads at once. Pushing and popping both come down to a single
+// compare-and-swap on the head pointer, retried if another thread got there first.
+//
+// The hard part is freeing popped nodes: another thread may have read the old head and be about
+// to look at its next pointer. Nodes are therefore retired through crossbeam's epoch-based
+// reclamation, which only frees them once every thread that could still hold a reference has
+// moved on.
+//
+// Peeking can't hand out a reference: epoch reclamation keeps a popped node's memory around, but
+// pop moves the element out (and its new owner may drop it) straight away, so a reference to the
+// top element could point at a String or Box whose contents have already been freed. Instead
+// peek_cloned copies the element while registered as a reader of its node, and pop waits for a
+// node's readers to finish before moving the element out.
+pub struct Stack<T> {
+    head: Atomic<Node<T>>,
+}
+
+struct Node<T> {
+    // Moved out by pop() before the node is retired, so the node itself must not drop it
+    elem: ManuallyDrop<T>,
+    next: Atomic<Node<T>>,
+    readers: AtomicUsize, // peek_cloned calls currently copying elem
+}
+
+// Registration as a reader of a node, undone on drop so that a panicking clone can't leave pop
+// waiting forever
+struct Reading<'a>(&'a AtomicUsize);
+
+impl<'a> Reading<'a> {
+    fn new(readers: &'a AtomicUsize) -> Self {
+        readers.fetch_add(1, Ordering::SeqCst);
+        Reading(readers)
+    }
+}
+
+impl Drop for Reading<'_> {
+    fn drop(&mut self) {
+        // Release so that pop, which acquires the count, can't move the element out from under us
+        self.0.fetch_sub(1, Ordering::Release);
+    }
+}
+
+// Elements only ever move between threads whole (pushed on one, popped on another), which is what
+// Send promises. The stack never hands out references to them, so T doesn't need to be Sync.
+unsafe impl<T: Send> Send for Stack<T> {}
+unsafe impl<T: Send> Sync for Stack<T> {}
+
+impl<T> Stack<T> {
+    pub fn new() -> Self {
+        Stack { head: Atomic::null() }
+    }
+
+    pub fn push(&self, elem: T) {
+        let mut node = Owned::new(Node { elem: ManuallyDrop::new(elem), next: Atomic::null(), readers: AtomicUsize::new(0) });
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Relaxed, &guard);
+            node.next.store(head, Ordering::Relaxed);
+            // Release so that a thread that sees the new head also sees the element inside it
+            match self.head.compare_exchange(head, node, Ordering::Release, Ordering::Relaxed, &guard) {
+                Ok(_) => return,
+                Err(err) => node = err.new,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            // Safe because head can't be freed while we are pinned
+            let node = unsafe { head.as_ref() }?;
+            let next = node.next.load(Ordering::Relaxed, &guard);
+            // SeqCst, paired with peek_cloned: either it sees that the node is no longer the head,
+            // or we see it registered as a reader
+            if self.head.compare_exchange(head, next, Ordering::SeqCst, Ordering::Relaxed, &guard).is_ok() {
+                // The CAS made us the only thread that can take this element, but a peek may still
+                // be copying it. Nobody new can start once the node is off the stack, so wait for
+                // those already there to finish.
+                while node.readers.load(Ordering::SeqCst) != 0 {
+                    hint::spin_loop();
+                }
+                // The node is freed later, once no pinned thread can still be reading it
+                unsafe {
+                    let elem = ptr::read(&*node.elem);
+                    guard.defer_destroy(head);
+                    return Some(elem);
+                }
+            }
+        }
+    }
+
+    // Copy of the top element
+    pub fn peek_cloned(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            // Safe because head can't be freed while we are pinned
+            let node = unsafe { head.as_ref() }?;
+            let _reading = Reading::new(&node.readers);
+            // If the node is still the head now that we are registered, any pop that takes it
+            // will wait for us. Otherwise it may already be gone, so start again from the new head.
+            // A popped node can't come back as the head, since its memory isn't reused while we
+            // are pinned.
+            if self.head.load(Ordering::SeqCst, &guard) == head {
+                return Some((*node.elem).clone());
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let guard = epoch::pin();
+        self.head.load(Ordering::Acquire, &guard).is_null()
+    }
+
+    // Heap memory held by the stack: one node allocation per element, counted by walking the
+    // stack. With other threads pushing and popping this is only a snapshot, and nodes that have
+    // been popped but not yet reclaimed aren't counted.
+    pub fn memory_footprint(&self) -> Footprint {
+        let guard = epoch::pin();
+        let mut count = 0;
+        let mut cur = self.head.load(Ordering::Acquire, &guard);
+        // Safe because no node we can reach is freed while we are pinned
+        while let Some(node) = unsafe { cur.as_ref() } {
+            count += 1;
+            cur = node.next.load(Ordering::Acquire, &guard);
+        }
+        Footprint::boxed::<Node<T>>(count)
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        // We have the only reference, so nothing else can be looking at the nodes and they can be
+        // freed right away rather than deferred
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut cur = self.head.load(Ordering::Relaxed, guard);
+            while let Some(node) = cur.as_ref() {
+                let next = node.next.load(Ordering::Relaxed, guard);
+                let mut owned = cur.into_owned();
+                ManuallyDrop::drop(&mut owned.elem);
+                cur = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Stack;
+    use std::collections::BTreeSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const PER_THREAD: usize = 20_000;
+
+    #[test]
+    fn basics() {
+        let stack = Stack::new();
+
+        // Check that popping from empty stack returns None
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+
+        // Check peeking, which leaves the element where it is
+        assert_eq!(stack.peek_cloned(), Some(3));
+        assert_eq!(stack.peek_cloned(), Some(3));
+
+        // Check normal removal
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+
+        // Check exhaustion
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.peek_cloned(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn memory_footprint() {
+        let stack = Stack::new();
+        assert_eq!(stack.memory_footprint().heap_bytes, 0);
+
+        for i in 0..5u64 {
+            stack.push(i);
+        }
+        stack.pop();
+        let footprint = stack.memory_footprint();
+        assert_eq!(footprint.nodes, 4);
+        assert_eq!(footprint.bytes_per_node, std::mem::size_of::<super::Node<u64>>());
+        assert_eq!(footprint.heap_bytes, 4 * footprint.bytes_per_node);
+    }
+
+    #[test]
+    fn non_clone_elements() {
+        let stack = Stack::new();
+        stack.push(String::from("bottom"));
+        stack.push(String::from("top"));
+        assert_eq!(stack.pop().as_deref(), Some("top"));
+        assert_eq!(stack.pop().as_deref(), Some("bottom"));
+    }
+
+    // Elements that own heap memory, popped and dropped on one thread while other threads are
+    // still reading the same nodes' next pointers. Every string has to come out intact.
+    #[test]
+    fn heap_elements_survive_concurrent_pops() {
+        let stack = Arc::new(Stack::new());
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let stack = Arc::clone(&stack);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                let mut popped = 0;
+                for i in 0..PER_THREAD {
+                    stack.push(format!("{}:{}", id, i));
+                    if let Some(elem) = stack.pop() {
+                        let (owner, index) = elem.split_once(':').expect("string was corrupted");
+                        assert!(owner.parse::<usize>().unwrap() < THREADS);
+                        assert!(index.parse::<usize>().unwrap() < PER_THREAD);
+                        popped += 1;
+                    }
+                }
+                popped
+            })
+        }).collect();
+
+        let popped: usize = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+        let mut left = 0;
+        while stack.pop().is_some() {
+            left += 1;
+        }
+        assert_eq!(popped + left, THREADS * PER_THREAD);
+    }
+
+    // Half the threads push and pop strings, dropping each one as soon as it is popped, while the
+    // other half peek as fast as they can. Every peeked copy has to be intact, which it wouldn't be
+    // if a peek could clone a string that a pop had already freed.
+    #[test]
+    fn peeks_alongside_pops() {
+        let stack = Arc::new(Stack::new());
+        let done = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let stack = Arc::clone(&stack);
+            let done = Arc::clone(&done);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                if id % 2 == 0 {
+                    for i in 0..PER_THREAD {
+                        stack.push(format!("{}:{}", id, i));
+                        drop(stack.pop());
+                    }
+                    done.fetch_add(1, Ordering::SeqCst);
+                } else {
+                    while done.load(Ordering::SeqCst) < THREADS / 2 {
+                        if let Some(elem) = stack.peek_cloned() {
+                            let (owner, index) = elem.split_once(':').expect("string was corrupted");
+                            assert_eq!(owner.parse::<usize>().unwrap() % 2, 0);
+                            assert!(index.parse::<usize>().unwrap() < PER_THREAD);
+                        }
+                    }
+                }
+            })
+        }).collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(stack.is_empty());
+    }
+
+    // Every thread pushes its own distinct values while popping concurrently. Everything popped
+    // plus whatever is left at the end has to be exactly what was pushed: nothing lost, nothing
+    // popped twice.
+    #[test]
+    fn no_lost_or_duplicated_elements() {
+        let stack = Arc::new(Stack::new());
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let stack = Arc::clone(&stack);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                let mut popped = Vec::new();
+                for i in 0..PER_THREAD {
+                    stack.push(id * PER_THREAD + i);
+                    // Pop a bit less often than we push, so the stack is rarely empty
+                    if i % 4 != 0 {
+                        popped.extend(stack.pop());
+                    }
+                }
+                popped
+            })
+        }).collect();
+
+        let mut seen = BTreeSet::new();
+        for handle in handles {
+            for elem in handle.join().unwrap() {
+                assert!(seen.insert(elem), "{} popped twice", elem);
+            }
+        }
+        while let Some(elem) = stack.pop() {
+            assert!(seen.insert(elem), "{} popped twice", elem);
+        }
+        assert_eq!(seen.len(), THREADS * PER_THREAD);
+        assert_eq!(seen.into_iter().collect::<Vec<_>>(), (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+
+    // Each thread's own pushes come back out in stack order relative to each other, even with
+    // other threads' elements interleaved
+    #[test]
+    fn per_thread_order() {
+        let stack = Arc::new(Stack::new());
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let stack = Arc::clone(&stack);
+            thread::spawn(move || {
+                for i in 0..PER_THREAD {
+                    stack.push((id, i));
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut last = [usize::MAX; THREADS];
+        while let Some((id, i)) = stack.pop() {
+            assert!(i < last[id], "thread {} pushed {} before {}", id, i, last[id]);
+            last[id] = i;
+        }
+        assert!(last.iter().all(|&i| i == 0));
+    }
+
+    // Elements that count their drops, to check each is dropped exactly once whether it is popped
+    // or left in the stack
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn every_element_dropped_once() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let stack = Arc::new(Stack::new());
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let stack = Arc::clone(&stack);
+            let drops = Arc::clone(&drops);
+            thread::spawn(move || {
+                for i in 0..PER_THREAD {
+                    stack.push(Counted(Arc::clone(&drops)));
+                    if (id + i) % 3 != 0 {
+                        drop(stack.pop());
+                    }
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stack = Arc::try_unwrap(stack).ok().unwrap();
+        drop(stack);
+        assert_eq!(drops.load(Ordering::SeqCst), THREADS * PER_THREAD);
+    }
+
+    #[test]
+    fn long_stack_drops() {
+        let stack = Stack::new();
+        for i in 0..100_000 {
+            stack.push(i);
+        }
+        drop(stack);
+    }
+}
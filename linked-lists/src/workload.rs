@@ -0,0 +1,843 @@
+use rand::distributions::{Distribution, Uniform, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::sorted_set::SortedSet;
+
+// A single operation against a sorted set of integers, as generated by the rand_driver example.
+// Workloads can be saved as traces (one operation per line, e.g. "insert 5", "delete 3" or
+// "range 4 9") and replayed later, so that any interesting run can be reproduced exactly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Insert(i32),
+    Delete(i32),
+    Contains(i32),
+    Range(i32, i32), // scan of every element in lo..=hi
+}
+
+// What running an Op returned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Bool(bool),
+    Scan { count: usize, sum: i64 }, // elements visited by a range scan
+}
+
+impl Op {
+    // Name used in traces and reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Op::Insert(_) => "insert",
+            Op::Delete(_) => "delete",
+            Op::Contains(_) => "contains",
+            Op::Range(..) => "range",
+        }
+    }
+
+    // The value the operation is about (the start, for range scans)
+    pub fn value(self) -> i32 {
+        match self {
+            Op::Insert(value) | Op::Delete(value) | Op::Contains(value) | Op::Range(value, _) => value,
+        }
+    }
+
+    // Run the operation against set, returning what the set returned
+    pub fn apply<S: SortedSet<i32>>(self, set: &mut S) -> Outcome {
+        match self {
+            Op::Insert(value) => Outcome::Bool(set.insert(value)),
+            Op::Delete(value) => Outcome::Bool(set.delete(&value)),
+            Op::Contains(value) => Outcome::Bool(set.contains(&value)),
+            Op::Range(lo, hi) => {
+                let (count, sum) = set.range(lo..=hi).fold((0, 0), |(count, sum), elem| {
+                    (count + 1, sum + *elem as i64)
+                });
+                Outcome::Scan { count, sum }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Op::Range(lo, hi) => write!(f, "range {} {}", lo, hi),
+            _ => write!(f, "{} {}", self.name(), self.value()),
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Bool(result) => write!(f, "{}", result),
+            Outcome::Scan { count, sum } => write!(f, "{} elements summing to {}", count, sum),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOpError(String);
+
+impl fmt::Display for ParseOpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid operation: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseOpError {}
+
+impl FromStr for Op {
+    type Err = ParseOpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let values = words
+            .map(|value| value.parse().map_err(|_| ParseOpError(format!("bad value {:?}", value))))
+            .collect::<Result<Vec<i32>, _>>()?;
+
+        match (name, &values[..]) {
+            ("insert", &[value]) => Ok(Op::Insert(value)),
+            ("delete", &[value]) => Ok(Op::Delete(value)),
+            ("contains", &[value]) => Ok(Op::Contains(value)),
+            ("range", &[lo, hi]) => Ok(Op::Range(lo, hi)),
+            ("insert" | "delete" | "contains" | "range", _) => {
+                Err(ParseOpError(format!("wrong number of values in {:?}", s)))
+            }
+            _ => Err(ParseOpError(format!("unknown operation {:?}", name))),
+        }
+    }
+}
+
+// Relative frequency of each kind of operation in a generated workload, written as e.g.
+// "insert=45,delete=45,contains=10". Kinds that aren't mentioned don't occur.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mix {
+    pub insert: u32,
+    pub delete: u32,
+    pub contains: u32,
+    pub range: u32,
+}
+
+impl Default for Mix {
+    // Even split of inserts and deletes, like the original rand_driver
+    fn default() -> Self {
+        Mix { insert: 50, delete: 50, contains: 0, range: 0 }
+    }
+}
+
+impl FromStr for Mix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mix = Mix { insert: 0, delete: 0, contains: 0, range: 0 };
+        for part in s.split(',') {
+            let (name, weight) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected <op>=<weight>, got {:?}", part))?;
+            let weight = weight.trim().parse().map_err(|_| format!("bad weight {:?}", weight))?;
+            match name.trim() {
+                "insert" => mix.insert = weight,
+                "delete" => mix.delete = weight,
+                "contains" => mix.contains = weight,
+                "range" => mix.range = weight,
+                name => return Err(format!("unknown operation {:?}", name)),
+            }
+        }
+        if mix.weights().iter().all(|&weight| weight == 0) {
+            return Err("at least one operation needs a non-zero weight".to_string());
+        }
+        Ok(mix)
+    }
+}
+
+impl fmt::Display for Mix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "insert={},delete={},contains={},range={}",
+            self.insert, self.delete, self.contains, self.range
+        )
+    }
+}
+
+impl Mix {
+    fn weights(&self) -> [u32; 4] {
+        [self.insert, self.delete, self.contains, self.range]
+    }
+}
+
+// How generated operations pick their values from 1..=value_range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyDist {
+    Uniform,
+    Zipf(f64),  // value k is picked with probability proportional to 1/k^s for exponent s
+    Ascending,  // 1, 2, ..., value_range, then around again (stresses appending at the tail)
+    Descending, // value_range, ..., 2, 1, then around again (stresses inserting at the head)
+    Hotspot { fraction: f64, probability: f64 }, // lowest fraction of values get probability of ops
+}
+
+impl FromStr for KeyDist {
+    type Err = String;
+
+    // Accepts "uniform", "zipf[:S]", "ascending", "descending" and "hotspot[:FRACTION:PROBABILITY]"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let name = parts.next().unwrap_or_default();
+        let params = parts
+            .map(|param| param.parse::<f64>().map_err(|_| format!("bad parameter {:?}", param)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let dist = match (name, &params[..]) {
+            ("uniform", []) => KeyDist::Uniform,
+            ("zipf", []) => KeyDist::Zipf(1.0),
+            ("zipf", &[exponent]) if exponent > 0.0 => KeyDist::Zipf(exponent),
+            ("ascending", []) => KeyDist::Ascending,
+            ("descending", []) => KeyDist::Descending,
+            ("hotspot", []) => KeyDist::Hotspot { fraction: 0.2, probability: 0.8 },
+            ("hotspot", &[fraction, probability])
+                if fraction > 0.0 && fraction <= 1.0 && (0.0..=1.0).contains(&probability) =>
+            {
+                KeyDist::Hotspot { fraction, probability }
+            }
+            ("uniform" | "zipf" | "ascending" | "descending" | "hotspot", _) => {
+                return Err(format!("bad parameters for {}: {:?}", name, s))
+            }
+            _ => return Err(format!("unknown distribution {:?}", name)),
+        };
+        Ok(dist)
+    }
+}
+
+impl fmt::Display for KeyDist {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyDist::Uniform => write!(f, "uniform"),
+            KeyDist::Zipf(exponent) => write!(f, "zipf:{}", exponent),
+            KeyDist::Ascending => write!(f, "ascending"),
+            KeyDist::Descending => write!(f, "descending"),
+            KeyDist::Hotspot { fraction, probability } => {
+                write!(f, "hotspot:{}:{}", fraction, probability)
+            }
+        }
+    }
+}
+
+// Precomputed state for drawing values from a KeyDist
+enum Keys {
+    Uniform(Uniform<i32>),
+    Zipf(Zipf),
+    Sequential { next: i32, step: i32 },
+    Hotspot { hot: Uniform<i32>, cold: Option<Uniform<i32>>, probability: f64 },
+}
+
+// Endless stream of random operations, reproducible from its seed
+pub struct Generator {
+    rng: StdRng,
+    ops: WeightedIndex<u32>,
+    keys: Keys,
+    value_range: i32,
+    range_width: i32, // number of values covered by each range scan
+}
+
+impl Generator {
+    pub fn new(seed: u64, value_range: i32, mix: &Mix, dist: KeyDist, range_width: i32) -> Self {
+        assert!(value_range >= 1, "value_range must be at least 1");
+        assert!(range_width >= 1, "range_width must be at least 1");
+
+        let keys = match dist {
+            KeyDist::Uniform => Keys::Uniform(Uniform::new_inclusive(1, value_range)),
+            KeyDist::Zipf(exponent) => Keys::Zipf(Zipf::new(value_range, exponent)),
+            KeyDist::Ascending => Keys::Sequential { next: 1, step: 1 },
+            KeyDist::Descending => Keys::Sequential { next: value_range, step: -1 },
+            KeyDist::Hotspot { fraction, probability } => {
+                let hot = ((value_range as f64 * fraction).round() as i32).clamp(1, value_range);
+                Keys::Hotspot {
+                    hot: Uniform::new_inclusive(1, hot),
+                    cold: (hot < value_range).then(|| Uniform::new_inclusive(hot + 1, value_range)),
+                    probability,
+                }
+            }
+        };
+
+        Generator {
+            rng: StdRng::seed_from_u64(seed),
+            ops: WeightedIndex::new(mix.weights()).expect("mix needs a non-zero weight"),
+            keys,
+            value_range,
+            range_width,
+        }
+    }
+
+    fn next_key(&mut self) -> i32 {
+        match &mut self.keys {
+            Keys::Uniform(dist) => dist.sample(&mut self.rng),
+            Keys::Zipf(zipf) => zipf.sample(&mut self.rng),
+            Keys::Sequential { next, step } => {
+                let key = *next;
+                *next += *step;
+                if *next > self.value_range {
+                    *next = 1;
+                } else if *next < 1 {
+                    *next = self.value_range;
+                }
+                key
+            }
+            Keys::Hotspot { hot, cold, probability } => match cold {
+                Some(cold) if !self.rng.gen_bool(*probability) => cold.sample(&mut self.rng),
+                _ => hot.sample(&mut self.rng),
+            },
+        }
+    }
+}
+
+// Zipf distribution over 1..=n, sampled by rejection-inversion (Hörmann and Derflinger, "Rejection-
+// inversion to generate variates from monotone discrete distributions", 1996). Unlike a table of
+// cumulative probabilities this takes constant time and space whatever n is, so the whole i32
+// range is fine. Values are drawn by inverting the integral of the continuous hat function
+// h(x) = x^-s, and the rare draws that land outside the distribution are rejected.
+struct Zipf {
+    n: i32,
+    exponent: f64,
+    h_integral_x1: f64, // hat integral at 1.5, minus h(1)
+    h_integral_n: f64,  // hat integral at n + 0.5
+    s: f64,             // values this close to their draw are always accepted
+}
+
+impl Zipf {
+    fn new(n: i32, exponent: f64) -> Self {
+        let mut zipf = Zipf { n, exponent, h_integral_x1: 0.0, h_integral_n: 0.0, s: 0.0 };
+        zipf.h_integral_x1 = zipf.h_integral(1.5) - 1.0;
+        zipf.h_integral_n = zipf.h_integral(n as f64 + 0.5);
+        zipf.s = 2.0 - zipf.h_integral_inverse(zipf.h_integral(2.5) - zipf.h(2.0));
+        zipf
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> i32 {
+        loop {
+            let u = self.h_integral_n + rng.gen::<f64>() * (self.h_integral_x1 - self.h_integral_n);
+            let x = self.h_integral_inverse(u);
+            let k = (x + 0.5).clamp(1.0, self.n as f64).floor();
+            if k - x <= self.s || u >= self.h_integral(k + 0.5) - self.h(k) {
+                return k as i32;
+            }
+        }
+    }
+
+    fn h(&self, x: f64) -> f64 {
+        (-self.exponent * x.ln()).exp()
+    }
+
+    // (x^(1-s) - 1) / (1-s), written so that it stays accurate as s approaches 1 (where it is ln x)
+    fn h_integral(&self, x: f64) -> f64 {
+        let log_x = x.ln();
+        expm1_over_x((1.0 - self.exponent) * log_x) * log_x
+    }
+
+    fn h_integral_inverse(&self, x: f64) -> f64 {
+        let t = (x * (1.0 - self.exponent)).max(-1.0);
+        (ln1p_over_x(t) * x).exp()
+    }
+}
+
+// ln(1 + x) / x, using its Taylor series near 0 where the division would lose precision
+fn ln1p_over_x(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.ln_1p() / x
+    } else {
+        1.0 - x * (0.5 - x * (1.0 / 3.0 - 0.25 * x))
+    }
+}
+
+// (e^x - 1) / x, likewise
+fn expm1_over_x(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.exp_m1() / x
+    } else {
+        1.0 + x * 0.5 * (1.0 + x / 3.0 * (1.0 + 0.25 * x))
+    }
+}
+
+impl Iterator for Generator {
+    type Item = Op;
+
+    fn next(&mut self) -> Option<Op> {
+        let kind = self.ops.sample(&mut self.rng);
+        let key = self.next_key();
+        Some(match kind {
+            0 => Op::Insert(key),
+            1 => Op::Delete(key),
+            2 => Op::Contains(key),
+            _ => Op::Range(key, key.saturating_add(self.range_width - 1)),
+        })
+    }
+}
+
+// Write ops to w, one per line. Any header lines are written first as comments.
+pub fn write_trace<W: Write>(mut w: W, header: &[String], ops: &[Op]) -> io::Result<()> {
+    for line in header {
+        writeln!(w, "# {}", line)?;
+    }
+    for op in ops {
+        writeln!(w, "{}", op)?;
+    }
+    w.flush()
+}
+
+// Read a trace written by write_trace. Blank lines and lines starting with '#' are skipped.
+pub fn read_trace<R: BufRead>(r: R) -> io::Result<Vec<Op>> {
+    let mut ops = Vec::new();
+    for (line_num, line) in r.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let op = line.parse().map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_num + 1, err))
+        })?;
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+// The header lines passed to write_trace: the comments before the first operation, without their
+// leading "# "
+pub fn read_trace_header<R: BufRead>(r: R) -> io::Result<Vec<String>> {
+    let mut header = Vec::new();
+    for line in r.lines() {
+        let line = line?;
+        let line = line.trim();
+        match line.strip_prefix('#') {
+            Some(comment) => header.push(comment.strip_prefix(' ').unwrap_or(comment).to_string()),
+            None if line.is_empty() => continue,
+            None => break,
+        }
+    }
+    Ok(header)
+}
+
+// First point at which a set stopped behaving like the BTreeSet oracle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub step: usize, // index of the offending operation
+    pub op: Op,
+    pub mismatch: Mismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    Return { expected: Outcome, actual: Outcome },
+    Len { expected: usize, actual: usize },
+    Contents { expected: Vec<i32>, actual: Vec<i32> },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "operation {} ({}): ", self.step + 1, self.op)?;
+        match &self.mismatch {
+            Mismatch::Return { expected, actual } => {
+                write!(f, "returned {} but BTreeSet returned {}", actual, expected)
+            }
+            Mismatch::Len { expected, actual } => {
+                write!(f, "length is {} but BTreeSet length is {}", actual, expected)
+            }
+            Mismatch::Contents { expected, actual } => {
+                write!(f, "contents are {:?} but BTreeSet contents are {:?}", actual, expected)
+            }
+        }
+    }
+}
+
+// Run ops against set while mirroring them into a BTreeSet, checking after every operation that
+// both returned the same thing and now hold the same elements
+pub fn check<S: SortedSet<i32>>(set: &mut S, ops: &[Op]) -> Result<(), Divergence> {
+    let mut oracle = BTreeSet::new();
+
+    for (step, &op) in ops.iter().enumerate() {
+        let diverged = |mismatch| Divergence { step, op, mismatch };
+
+        let (expected, actual) = (op.apply(&mut oracle), op.apply(set));
+        if expected != actual {
+            return Err(diverged(Mismatch::Return { expected, actual }));
+        }
+        if oracle.len() != set.len() {
+            return Err(diverged(Mismatch::Len { expected: oracle.len(), actual: set.len() }));
+        }
+        if !oracle.iter().eq(set.iter()) {
+            let expected = oracle.iter().copied().collect();
+            let actual = set.iter().copied().collect();
+            return Err(diverged(Mismatch::Contents { expected, actual }));
+        }
+    }
+
+    Ok(())
+}
+
+// Given a history of ops that makes a fresh set (from new) diverge from the oracle, find a
+// minimal sub-history that still does: removing any single op from the result makes the
+// divergence go away. Chunks of ops are tried for removal before individual ones, so long
+// histories shrink quickly.
+pub fn shrink<S: SortedSet<i32>>(new: impl Fn() -> S, ops: &[Op]) -> Vec<Op> {
+    let diverges = |ops: &[Op]| check(&mut new(), ops).err();
+
+    // Nothing after the first divergence matters
+    let mut ops = match diverges(ops) {
+        Some(divergence) => ops[..=divergence.step].to_vec(),
+        None => return ops.to_vec(),
+    };
+
+    let mut chunk = ops.len().div_ceil(2);
+    loop {
+        let mut removed_any = false;
+        let mut start = 0;
+        while start < ops.len() {
+            let end = (start + chunk).min(ops.len());
+            let candidate = [&ops[..start], &ops[end..]].concat();
+            match diverges(&candidate) {
+                Some(divergence) => {
+                    ops = candidate;
+                    ops.truncate(divergence.step + 1);
+                    removed_any = true;
+                }
+                None => start = end,
+            }
+        }
+
+        // Halve the chunk size each pass, then keep going with single ops until none of them
+        // can be removed any more
+        if chunk > 1 {
+            chunk = chunk.div_ceil(2);
+        } else if !removed_any {
+            break;
+        }
+    }
+
+    ops
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check, read_trace, read_trace_header, shrink, write_trace, Generator, KeyDist, Mismatch, Mix, Op, Outcome};
+    use crate::cs120::List;
+    use crate::sorted_set::SortedSet;
+    use std::collections::BTreeSet;
+    use std::ops::RangeBounds;
+
+    #[test]
+    fn parse() {
+        assert_eq!("insert 5".parse(), Ok(Op::Insert(5)));
+        assert_eq!("  delete   -3 ".parse(), Ok(Op::Delete(-3)));
+        assert!("insert".parse::<Op>().is_err());
+        assert!("insert five".parse::<Op>().is_err());
+        assert!("insert 5 6".parse::<Op>().is_err());
+        assert!("remove 5".parse::<Op>().is_err());
+        assert_eq!("contains 4".parse(), Ok(Op::Contains(4)));
+        assert_eq!("range 2 8".parse(), Ok(Op::Range(2, 8)));
+        assert!("range 2".parse::<Op>().is_err());
+        assert!("contains 2 8".parse::<Op>().is_err());
+        assert!("".parse::<Op>().is_err());
+
+        for op in [Op::Insert(7), Op::Delete(i32::MIN), Op::Contains(0), Op::Range(-1, 3)] {
+            assert_eq!(op.to_string().parse(), Ok(op));
+        }
+    }
+
+    #[test]
+    fn trace_round_trip() {
+        let ops = [Op::Insert(3), Op::Insert(1), Op::Delete(3), Op::Delete(4)];
+        let mut trace = Vec::new();
+        write_trace(&mut trace, &["seed: 42".to_string()], &ops).unwrap();
+
+        assert_eq!(
+            String::from_utf8(trace.clone()).unwrap(),
+            "# seed: 42\ninsert 3\ninsert 1\ndelete 3\ndelete 4\n"
+        );
+        assert_eq!(read_trace(&trace[..]).unwrap(), ops);
+    }
+
+    #[test]
+    fn trace_header() {
+        let header = ["rand_driver --seed 42".to_string(), "warmup ops: 2".to_string()];
+        let mut trace = Vec::new();
+        write_trace(&mut trace, &header, &[Op::Insert(1), Op::Insert(2)]).unwrap();
+        trace.extend_from_slice(b"# not part of the header\ninsert 3\n");
+        assert_eq!(read_trace_header(&trace[..]).unwrap(), header);
+        assert!(read_trace_header("insert 1\n".as_bytes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn bad_trace_reports_line() {
+        let trace = "# header\ninsert 1\n\ninsert x\n";
+        let err = read_trace(trace.as_bytes()).unwrap_err();
+        assert!(err.to_string().starts_with("line 4:"), "{}", err);
+    }
+
+    // Example of turning a recorded trace into a regression test
+    #[test]
+    fn replay_trace() {
+        let trace = "insert 5\ninsert 1\ninsert 9\ninsert 5\ndelete 1\ndelete 1\ninsert 0\ndelete 9\n";
+        let ops = read_trace(trace.as_bytes()).unwrap();
+
+        let mut list = List::new();
+        let mut model = BTreeSet::new();
+        for op in ops {
+            assert_eq!(op.apply(&mut list), op.apply(&mut model), "{}", op);
+        }
+        assert!(list.iter().eq(model.iter()));
+    }
+
+    // BTreeSet with a bug reminiscent of a missing special case: deleting the smallest element
+    // does nothing (but still reports success) when there are at least three elements
+    #[derive(Default)]
+    struct Buggy(BTreeSet<i32>);
+
+    impl SortedSet<i32> for Buggy {
+        type Iter<'a> = <BTreeSet<i32> as SortedSet<i32>>::Iter<'a>;
+        type Range<'a, R> = <BTreeSet<i32> as SortedSet<i32>>::Range<'a, R> where R: RangeBounds<i32>;
+
+        fn insert(&mut self, elem: i32) -> bool {
+            self.0.insert(elem)
+        }
+
+        fn delete(&mut self, elem: &i32) -> bool {
+            if self.0.len() >= 3 && self.0.first() == Some(elem) {
+                return true;
+            }
+            self.0.remove(elem)
+        }
+
+        fn contains(&self, elem: &i32) -> bool {
+            self.0.contains(elem)
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn iter(&self) -> Self::Iter<'_> {
+            self.0.iter()
+        }
+
+        fn range<R: RangeBounds<i32>>(&self, range: R) -> Self::Range<'_, R> {
+            SortedSet::range(&self.0, range)
+        }
+    }
+
+    #[test]
+    fn check_passes_correct_sets() {
+        let ops: Vec<Op> = (0..500)
+            .map(|i| if i % 3 == 0 { Op::Delete(i % 17) } else { Op::Insert(i % 17) })
+            .collect();
+        assert_eq!(check(&mut List::new(), &ops), Ok(()));
+        assert_eq!(check(&mut BTreeSet::new(), &ops), Ok(()));
+    }
+
+    #[test]
+    fn check_finds_divergence() {
+        let ops = [Op::Insert(1), Op::Insert(2), Op::Delete(1), Op::Insert(3), Op::Delete(1)];
+        assert_eq!(check(&mut Buggy::default(), &ops), Ok(()));
+
+        let ops = [Op::Insert(2), Op::Insert(1), Op::Insert(3), Op::Delete(1), Op::Insert(4)];
+        let divergence = check(&mut Buggy::default(), &ops).unwrap_err();
+        assert_eq!(divergence.step, 3);
+        assert_eq!(divergence.op, Op::Delete(1));
+        assert_eq!(divergence.mismatch, Mismatch::Len { expected: 2, actual: 3 });
+        assert_eq!(
+            divergence.to_string(),
+            "operation 4 (delete 1): length is 3 but BTreeSet length is 2"
+        );
+    }
+
+    #[test]
+    fn shrink_finds_minimal_history() {
+        // Lots of noise around the three inserts and one delete that trigger the bug
+        let mut ops = Vec::new();
+        for i in 0..50 {
+            ops.push(Op::Insert(100 + i));
+            ops.push(Op::Delete(100 + i));
+        }
+        ops.extend([Op::Insert(5), Op::Delete(7), Op::Insert(9), Op::Insert(6)]);
+        ops.extend([Op::Delete(9), Op::Insert(8), Op::Delete(5), Op::Insert(1), Op::Delete(1)]);
+        assert!(check(&mut Buggy::default(), &ops).is_err());
+
+        let minimal = shrink(Buggy::default, &ops);
+        assert_eq!(minimal.len(), 4, "{:?}", minimal);
+        assert!(check(&mut Buggy::default(), &minimal).is_err());
+        for i in 0..minimal.len() {
+            let mut fewer = minimal.clone();
+            fewer.remove(i);
+            assert_eq!(check(&mut Buggy::default(), &fewer), Ok(()));
+        }
+    }
+
+    #[test]
+    fn shrink_leaves_passing_history_alone() {
+        let ops = [Op::Insert(1), Op::Delete(1)];
+        assert_eq!(shrink(List::new, &ops), ops);
+    }
+
+    #[test]
+    fn apply_outcomes() {
+        let mut list = List::new();
+        assert_eq!(Op::Insert(3).apply(&mut list), Outcome::Bool(true));
+        assert_eq!(Op::Insert(5).apply(&mut list), Outcome::Bool(true));
+        assert_eq!(Op::Insert(9).apply(&mut list), Outcome::Bool(true));
+        assert_eq!(Op::Contains(5).apply(&mut list), Outcome::Bool(true));
+        assert_eq!(Op::Contains(4).apply(&mut list), Outcome::Bool(false));
+        assert_eq!(Op::Range(4, 9).apply(&mut list), Outcome::Scan { count: 2, sum: 14 });
+        assert_eq!(Op::Range(10, 20).apply(&mut list), Outcome::Scan { count: 0, sum: 0 });
+        assert_eq!(Op::Delete(5).apply(&mut list), Outcome::Bool(true));
+        assert_eq!(Outcome::Scan { count: 2, sum: 14 }.to_string(), "2 elements summing to 14");
+    }
+
+    #[test]
+    fn parse_mix() {
+        assert_eq!(Mix::default().to_string(), "insert=50,delete=50,contains=0,range=0");
+        assert_eq!(
+            "insert=3, delete=1,range=2".parse(),
+            Ok(Mix { insert: 3, delete: 1, contains: 0, range: 2 })
+        );
+        let mix = Mix { insert: 1, delete: 2, contains: 3, range: 4 };
+        assert_eq!(mix.to_string().parse(), Ok(mix));
+
+        assert!("insert".parse::<Mix>().is_err());
+        assert!("insert=x".parse::<Mix>().is_err());
+        assert!("insert=1,lookup=2".parse::<Mix>().is_err());
+        assert!("insert=0,delete=0".parse::<Mix>().is_err());
+    }
+
+    #[test]
+    fn parse_key_dist() {
+        assert_eq!("uniform".parse(), Ok(KeyDist::Uniform));
+        assert_eq!("zipf".parse(), Ok(KeyDist::Zipf(1.0)));
+        assert_eq!("zipf:1.5".parse(), Ok(KeyDist::Zipf(1.5)));
+        assert_eq!("ascending".parse(), Ok(KeyDist::Ascending));
+        assert_eq!("descending".parse(), Ok(KeyDist::Descending));
+        assert_eq!("hotspot".parse(), Ok(KeyDist::Hotspot { fraction: 0.2, probability: 0.8 }));
+        assert_eq!(
+            "hotspot:0.1:0.9".parse(),
+            Ok(KeyDist::Hotspot { fraction: 0.1, probability: 0.9 })
+        );
+        for dist in [KeyDist::Zipf(0.5), KeyDist::Hotspot { fraction: 0.5, probability: 0.25 }] {
+            assert_eq!(dist.to_string().parse(), Ok(dist));
+        }
+
+        for bad in ["normal", "zipf:0", "zipf:x", "uniform:1", "hotspot:0.5", "hotspot:2:0.5"] {
+            assert!(bad.parse::<KeyDist>().is_err(), "{}", bad);
+        }
+    }
+
+    fn generate(seed: u64, value_range: i32, mix: &str, dist: &str, n: usize) -> Vec<Op> {
+        let mix = mix.parse().unwrap();
+        Generator::new(seed, value_range, &mix, dist.parse().unwrap(), 5).take(n).collect()
+    }
+
+    // How many of ops have each value in 1..=value_range (index 0 is unused)
+    fn histogram(ops: &[Op], value_range: i32) -> Vec<usize> {
+        let mut counts = vec![0; value_range as usize + 1];
+        for op in ops {
+            counts[op.value() as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn generator_is_reproducible() {
+        let a = generate(9, 100, "insert=1,delete=1,contains=1,range=1", "zipf", 1000);
+        let b = generate(9, 100, "insert=1,delete=1,contains=1,range=1", "zipf", 1000);
+        let c = generate(10, 100, "insert=1,delete=1,contains=1,range=1", "zipf", 1000);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn generator_mix() {
+        let ops = generate(1, 1000, "insert=6,contains=3,range=1", "uniform", 10000);
+        let count = |name| ops.iter().filter(|op| op.name() == name).count();
+        assert_eq!(count("delete"), 0);
+        assert!((5700..6300).contains(&count("insert")), "{}", count("insert"));
+        assert!((2700..3300).contains(&count("contains")), "{}", count("contains"));
+        assert!((800..1200).contains(&count("range")), "{}", count("range"));
+
+        // Range scans cover range_width values starting at the key
+        for op in &ops {
+            if let Op::Range(lo, hi) = op {
+                assert_eq!(hi - lo, 4);
+            }
+        }
+    }
+
+    #[test]
+    fn generator_uniform_covers_whole_range() {
+        // Values are not limited to 1..=100, unlike the original driver
+        let ops = generate(2, 1000, "insert=1", "uniform", 20000);
+        let counts = histogram(&ops, 1000);
+        assert_eq!(counts[0], 0);
+        assert!(counts[1..].iter().all(|&count| count > 0));
+        assert!(counts[901..].iter().sum::<usize>() > 1500);
+    }
+
+    #[test]
+    fn generator_sequential() {
+        let ops = generate(3, 4, "insert=1", "ascending", 10);
+        let values: Vec<i32> = ops.iter().map(|op| op.value()).collect();
+        assert_eq!(values, [1, 2, 3, 4, 1, 2, 3, 4, 1, 2]);
+
+        let ops = generate(3, 4, "insert=1,delete=1", "descending", 6);
+        let values: Vec<i32> = ops.iter().map(|op| op.value()).collect();
+        assert_eq!(values, [4, 3, 2, 1, 4, 3]);
+    }
+
+    #[test]
+    fn generator_zipf() {
+        let ops = generate(4, 100, "insert=1", "zipf", 50000);
+        let counts = histogram(&ops, 100);
+
+        // With exponent 1, value 1 is picked about twice as often as value 2, and about 19% of
+        // the time overall (1 / H(100), where H(100) is about 5.19)
+        assert!((8500..10800).contains(&counts[1]), "{}", counts[1]);
+        let ratio = counts[1] as f64 / counts[2] as f64;
+        assert!((1.8..2.2).contains(&ratio), "{}", ratio);
+        assert!(counts[1..11].iter().sum::<usize>() > counts[11..].iter().sum::<usize>());
+        assert!(ops.iter().all(|op| (1..=100).contains(&op.value())));
+    }
+
+    #[test]
+    fn generator_zipf_steeper() {
+        // With exponent 2, value 1 is picked 1 / zeta(2), about 61% of the time, and value 2 a
+        // quarter as often
+        let ops = generate(6, 1000, "insert=1", "zipf:2", 50000);
+        let counts = histogram(&ops, 1000);
+        assert!((29800..31000).contains(&counts[1]), "{}", counts[1]);
+        let ratio = counts[1] as f64 / counts[2] as f64;
+        assert!((3.7..4.3).contains(&ratio), "{}", ratio);
+    }
+
+    #[test]
+    fn generator_zipf_whole_range() {
+        // No table is built, so the largest range is as cheap as any other. Value 1 is picked
+        // 1 / H(i32::MAX) of the time, about 4.5%.
+        let ops = generate(7, i32::MAX, "insert=1", "zipf", 20000);
+        let ones = ops.iter().filter(|op| op.value() == 1).count();
+        assert!((780..1040).contains(&ones), "{}", ones);
+        assert!(ops.iter().all(|op| op.value() >= 1));
+        assert!(ops.iter().any(|op| op.value() > 1_000_000));
+    }
+
+    #[test]
+    fn generator_hotspot() {
+        let ops = generate(5, 100, "insert=1", "hotspot:0.1:0.9", 20000);
+        let counts = histogram(&ops, 100);
+        let hot: usize = counts[1..=10].iter().sum();
+        assert!((17600..18400).contains(&hot), "{}", hot);
+        assert!(counts[11..].iter().all(|&count| count > 0));
+
+        // A hotspot covering everything is just uniform
+        let ops = generate(5, 10, "insert=1", "hotspot:1:0.5", 1000);
+        assert!(ops.iter().all(|op| (1..=10).contains(&op.value())));
+    }
+}
@@ -0,0 +1,88 @@
+// Tests that need to control the global allocator. They live in their own test binary so that
+// FailingAlloc only replaces the allocator here, not for the library's unit tests.
+
+use linked_lists::cs120::{Error, List};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Allocator that fails every allocation made by a thread while that thread has FAIL_ALLOCS set,
+// and otherwise defers to the system allocator. It also keeps track of how many bytes each thread
+// has live, so tests can see what a list really holds.
+struct FailingAlloc;
+
+thread_local! {
+    static FAIL_ALLOCS: Cell<bool> = const { Cell::new(false) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+fn track(bytes: isize) {
+    let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + bytes));
+}
+
+unsafe impl GlobalAlloc for FailingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if FAIL_ALLOCS.try_with(|fail| fail.get()).unwrap_or(false) {
+            return std::ptr::null_mut();
+        }
+        track(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: FailingAlloc = FailingAlloc;
+
+fn contents(list: &List<i32>) -> Vec<i32> {
+    list.iter().copied().collect()
+}
+
+#[test]
+fn alloc_failure() {
+    let mut list = List::new();
+    list.insert(10);
+    list.insert(30);
+
+    // Every insertion point should report the failure and leave the list intact
+    FAIL_ALLOCS.with(|fail| fail.set(true));
+    let results = [list.try_insert(5), list.try_insert(20), list.try_insert(40)];
+    let duplicate = list.try_insert(10);
+    FAIL_ALLOCS.with(|fail| fail.set(false));
+
+    assert_eq!(results, [Err(Error::AllocFailed); 3]);
+    assert_eq!(duplicate, Err(Error::Duplicate));
+    assert_eq!(contents(&list), [10, 30]);
+    assert_eq!(list.len(), 2);
+
+    let mut empty = List::new();
+    FAIL_ALLOCS.with(|fail| fail.set(true));
+    let result = empty.try_insert(1);
+    FAIL_ALLOCS.with(|fail| fail.set(false));
+    assert_eq!(result, Err(Error::AllocFailed));
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn memory_footprint() {
+    let empty = List::<i32>::new().memory_footprint();
+    assert_eq!(empty.nodes, 0);
+    assert_eq!(empty.heap_bytes, 0);
+
+    // The footprint should be exactly what the allocator handed out for the list
+    let before = LIVE_BYTES.with(Cell::get);
+    let mut list = List::new();
+    for elem in [20, 10, 30, 25, 5] {
+        list.insert(elem);
+    }
+    list.delete(&25);
+    let allocated = LIVE_BYTES.with(Cell::get) - before;
+
+    let footprint = list.memory_footprint();
+    assert_eq!(footprint.nodes, 4);
+    assert_eq!(footprint.heap_bytes, 4 * footprint.bytes_per_node);
+    assert_eq!(footprint.heap_bytes as isize, allocated);
+}